@@ -0,0 +1,157 @@
+#[allow(dead_code)]
+#[path = "../src/testing.rs"]
+mod testing;
+
+use aoc2025::day4::{self, Backend};
+use std::io::Cursor;
+use std::thread;
+use std::time::{Duration, Instant};
+use testing::{XorShift, random_rolls};
+
+const SIZE: usize = 5000;
+const SPARSE_SIZE: usize = 50_000;
+const SPARSE_BLOCKS: usize = 3000;
+const SPARSE_BLOCK_SIZE: usize = 40;
+
+// Rolls are placed pseudo-randomly with a density of about 70 %, which leaves a dense core
+// that erodes over many rounds.
+fn generate_grid(rows: usize, cols: usize) -> String {
+    random_rolls(0x9e3779b97f4a7c15, rows, cols, 70)
+}
+
+// Dense square blocks of rolls scattered over a large, otherwise empty area, as a list of
+// coordinates
+fn generate_coordinates(size: usize, blocks: usize, block_size: usize) -> String {
+    let mut random = XorShift::new(0x2545f4914f6cdd1d);
+    let mut next = || random.next_u64();
+    let mut coordinates = String::new();
+    for _ in 0..blocks {
+        let top = next() as usize % (size - block_size);
+        let left = next() as usize % (size - block_size);
+        for row in top..top + block_size {
+            for col in left..left + block_size {
+                if next() % 10 < 7 {
+                    coordinates.push_str(&format!("{},{}\n", row, col));
+                }
+            }
+        }
+    }
+    coordinates
+}
+
+fn bench_sparse() {
+    let input = generate_coordinates(SPARSE_SIZE, SPARSE_BLOCKS, SPARSE_BLOCK_SIZE);
+
+    let now = Instant::now();
+    let mut grid = day4::parse_coordinates(&mut Cursor::new(input.as_bytes())).unwrap();
+    let parsed = now.elapsed();
+    let rolls = grid.count_rolls();
+
+    let now = Instant::now();
+    let accessible = grid.accessible().len();
+    let counted = now.elapsed();
+
+    let now = Instant::now();
+    let rounds = grid.remove_all();
+    let elapsed = now.elapsed();
+
+    println!(
+        "Sparse {}x{} grid with {} rolls: {} accessible rolls",
+        SPARSE_SIZE, SPARSE_SIZE, rolls, accessible
+    );
+    println!(
+        "Sparse {}x{} grid: removed {} rolls in {} rounds",
+        SPARSE_SIZE,
+        SPARSE_SIZE,
+        rounds.iter().sum::<usize>(),
+        rounds.len()
+    );
+    println!("Sparse parse: {:.2?}", parsed);
+    println!("Sparse accessible: {:.2?}", counted);
+    println!("Sparse remove: {:.2?}", elapsed);
+}
+
+// Times finding the accessible rolls and removing all of them with the given number of threads
+fn time_threads(input: &str, threads: usize) -> (Duration, Duration, usize) {
+    let mut grid = day4::parse_grid(&mut Cursor::new(input.as_bytes()))
+        .unwrap()
+        .with_threads(threads);
+
+    let now = Instant::now();
+    grid.accessible_grid();
+    let counted = now.elapsed();
+
+    let now = Instant::now();
+    let total_removed = grid.remove_all_recorded().total_removed();
+    (counted, now.elapsed(), total_removed)
+}
+
+fn bench_threads(input: &str, threads: usize) {
+    let (single_counted, single_removed, single_total) = time_threads(input, 1);
+    let (counted, removed, total) = time_threads(input, threads);
+    assert_eq!(
+        total, single_total,
+        "the result depends on the number of threads"
+    );
+
+    println!(
+        "Accessible with {} threads: {:.2?} ({:.2}x)",
+        threads,
+        counted,
+        single_counted.as_secs_f64() / counted.as_secs_f64()
+    );
+    println!(
+        "Remove with {} threads: {:.2?} ({:.2}x)",
+        threads,
+        removed,
+        single_removed.as_secs_f64() / removed.as_secs_f64()
+    );
+}
+
+// The number of threads to compare against a single thread can be given as an argument, e.g.
+// `cargo bench --bench day4 -- 8`, and defaults to the available parallelism
+fn main() {
+    let threads = std::env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .or_else(|| {
+            thread::available_parallelism()
+                .ok()
+                .map(|threads| threads.get())
+        })
+        .unwrap_or(1);
+    let input = generate_grid(SIZE, SIZE);
+
+    let now = Instant::now();
+    let mut grid = day4::parse_grid(&mut Cursor::new(input.as_bytes())).unwrap();
+    let parsed = now.elapsed();
+
+    let now = Instant::now();
+    let accessible = grid.count_accessible();
+    let counted = now.elapsed();
+
+    let now = Instant::now();
+    let mut rounds = 0;
+    let mut total_removed = 0;
+    loop {
+        let removed = grid.remove();
+        if removed == 0 {
+            break;
+        }
+        rounds += 1;
+        total_removed += removed;
+    }
+    let elapsed = now.elapsed();
+
+    println!("{}x{} grid: {} accessible rolls", SIZE, SIZE, accessible);
+    println!(
+        "{}x{} grid: removed {} rolls in {} rounds",
+        SIZE, SIZE, total_removed, rounds
+    );
+    println!("Parse: {:.2?}", parsed);
+    println!("Accessible: {:.2?}", counted);
+    println!("Remove: {:.2?}", elapsed);
+
+    bench_threads(&input, threads);
+    bench_sparse();
+}
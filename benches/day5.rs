@@ -0,0 +1,86 @@
+#[allow(dead_code)]
+#[path = "../src/testing.rs"]
+mod testing;
+
+use aoc2025::interval_set::IntervalSet;
+use std::ops::RangeInclusive;
+use std::time::Instant;
+use testing::XorShift;
+
+const RANGES: usize = 1_000_000;
+const INGREDIENTS: usize = 1_000_000;
+// Ingredients checked against every range one by one, which is too slow for all of them
+const NAIVE_INGREDIENTS: usize = 100;
+const MAX_ID: u64 = 1_000_000_000_000_000;
+const MAX_RANGE_LEN: u64 = 200_000_000;
+
+fn main() {
+    let mut random = XorShift::new(0x9e3779b97f4a7c15);
+    let ranges: Vec<RangeInclusive<u64>> = (0..RANGES)
+        .map(|_| {
+            let start = random.next_u64() % MAX_ID;
+            start..=start + random.next_u64() % MAX_RANGE_LEN
+        })
+        .collect();
+    let ingredients: Vec<u64> = (0..INGREDIENTS)
+        .map(|_| random.next_u64() % MAX_ID)
+        .collect();
+
+    let now = Instant::now();
+    let fresh: IntervalSet = ranges.iter().cloned().collect();
+    let merged = now.elapsed();
+
+    let now = Instant::now();
+    let searched = ingredients
+        .iter()
+        .filter(|&&ingredient| fresh.contains(ingredient))
+        .count();
+    let binary_search = now.elapsed();
+
+    let now = Instant::now();
+    let mut sorted = ingredients.clone();
+    sorted.sort_unstable();
+    let swept = fresh.count_sorted(&sorted);
+    let sweep = now.elapsed();
+    assert_eq!(searched, swept);
+
+    // Streams that are already sorted can be matched one value at a time without sorting
+    let now = Instant::now();
+    let mut cursor = fresh.cursor();
+    let streamed = sorted
+        .iter()
+        .filter(|&&ingredient| cursor.contains(ingredient))
+        .count();
+    let stream = now.elapsed();
+    assert_eq!(searched, streamed);
+
+    let now = Instant::now();
+    let naive = ingredients[..NAIVE_INGREDIENTS]
+        .iter()
+        .filter(|ingredient| ranges.iter().any(|range| range.contains(ingredient)))
+        .count();
+    let scan = now.elapsed();
+    let expected = ingredients[..NAIVE_INGREDIENTS]
+        .iter()
+        .filter(|&&ingredient| fresh.contains(ingredient))
+        .count();
+    assert_eq!(naive, expected);
+
+    println!(
+        "{} ranges merged into {} intervals, {} of {} ingredients fresh",
+        RANGES,
+        fresh.interval_count(),
+        searched,
+        INGREDIENTS
+    );
+    println!("Merge: {:.2?}", merged);
+    println!("Binary search: {:.2?}", binary_search);
+    println!("Sort and sweep: {:.2?}", sweep);
+    println!("Cursor over sorted stream: {:.2?}", stream);
+    println!(
+        "Scan of all ranges: {:.2?} for {} ingredients, about {:.2?} for all of them",
+        scan,
+        NAIVE_INGREDIENTS,
+        scan * (INGREDIENTS / NAIVE_INGREDIENTS) as u32
+    );
+}
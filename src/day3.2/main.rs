@@ -1,90 +1,93 @@
+use aoc2025::day3::{self, BankError, JoltageSum};
 use std::io::BufRead;
 
+const DEFAULT_BATTERIES: usize = 12;
+
+const USAGE: &str = "\
+Usage: day3_2 [BATTERIES]
+  BATTERIES          number of batteries to turn on in each bank, 12 by default";
+
 fn main() {
-    let mut handle = std::io::stdin().lock();
-    let result = solve(&mut handle);
+    let batteries = match parse_batteries(std::env::args().nth(1)) {
+        Ok(batteries) => batteries,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, USAGE);
+            std::process::exit(2);
+        }
+    };
 
-    println!("{}", result);
+    let mut handle = std::io::stdin().lock();
+    match solve(&mut handle, batteries) {
+        Ok(result) => println!("{}", result),
+        Err(error) => {
+            eprintln!("Invalid input: {}", error);
+            std::process::exit(1);
+        }
+    }
 }
 
-fn solve(reader: &mut dyn BufRead) -> i64 {
-    reader
-        .lines()
-        .filter_map(|line| line.ok())
-        .map(|bank| max_joltage_of_bank(&bank))
-        .sum()
+fn parse_batteries(arg: Option<String>) -> Result<usize, String> {
+    let Some(arg) = arg else {
+        return Ok(DEFAULT_BATTERIES);
+    };
+    match arg.parse() {
+        Ok(batteries) if batteries > 0 => Ok(batteries),
+        _ => Err(format!(
+            "invalid number of batteries {:?}, expected a positive integer",
+            arg
+        )),
+    }
 }
 
-fn max_joltage_of_bank(bank: &str) -> i64 {
-    // For performance, reuse this vector for generating variants
-    let mut temp_variant_digits = Vec::with_capacity(12);
-
-    bank.chars()
-        .flat_map(|c| c.to_digit(10))
-        .map(|d| d as i64)
-        .fold((0, vec![]), |(max, digits), digit| {
-            if digits.len() < 2 {
-                let mut new_digits = digits;
-                new_digits.push(digit);
-                let new_max = max * 10 + digit;
-                return (new_max, new_digits);
-            }
-
-            let len = digits.len();
-            let mut max_new = max;
-            let mut digits_new = digits.clone();
-
-            for cut_index in 0..len {
-                temp_variant_digits.clear();
-                temp_variant_digits.extend_from_slice(&digits[..cut_index]);
-                temp_variant_digits.extend_from_slice(&digits[cut_index..]);
-
-                if len < 12 {
-                    temp_variant_digits.push(digit);
-                } else {
-                    temp_variant_digits.remove(cut_index);
-                    temp_variant_digits.push(digit);
-                }
-
-                let variant = temp_variant_digits.iter().fold(0, |acc, &d| acc * 10 + d);
-                if variant > max_new {
-                    max_new = variant;
-                    digits_new = temp_variant_digits.clone();
-                }
-            }
-
-            (max_new, digits_new)
-        })
-        .0
+fn solve(reader: &mut dyn BufRead, batteries: usize) -> Result<JoltageSum, BankError> {
+    day3::total_max_joltage(reader, batteries)
 }
 
 #[cfg(test)]
 mod tests {
-    use rstest::rstest;
+    use super::DEFAULT_BATTERIES;
 
     #[test]
     fn test_sample_input() {
         let input = b"987654321111111\n811111111111119\n234234234234278\n818181911112111\n";
         let mut reader: &[u8] = &input[..];
-        let result = super::solve(&mut reader);
-        assert_eq!(result, 3121910778619);
+        let result = super::solve(&mut reader, DEFAULT_BATTERIES).unwrap();
+        assert_eq!(result.to_u128(), Some(3121910778619));
     }
 
     #[test]
     fn test_input_from_file() {
         let input = include_str!("input.txt");
         let mut reader = input.as_bytes();
-        let result = super::solve(&mut reader);
-        assert_eq!(result, 172740584266849);
+        let result = super::solve(&mut reader, DEFAULT_BATTERIES).unwrap();
+        assert_eq!(result.to_u128(), Some(172740584266849));
+    }
+
+    #[test]
+    fn test_sample_input_all_batteries() {
+        let input = b"987654321111111\n811111111111119\n234234234234278\n818181911112111\n";
+        let mut reader: &[u8] = &input[..];
+        let result = super::solve(&mut reader, 15).unwrap();
+        assert_eq!(
+            result.to_string(),
+            (987654321111111u128 + 811111111111119 + 234234234234278 + 818181911112111).to_string()
+        );
     }
 
-    #[rstest]
-    #[case("987654321111111\n", 987654321111)]
-    #[case("811111111111119\n", 811111111119)]
-    #[case("234234234234278\n", 434234234278)]
-    #[case("818181911112111\n", 888911112111)]
-    fn test_max_joltage_of_bank(#[case] input: &str, #[case] expected: i64) {
-        let result = super::max_joltage_of_bank(input);
-        assert_eq!(result, expected);
+    #[test]
+    fn test_parse_batteries() {
+        assert_eq!(super::parse_batteries(None), Ok(DEFAULT_BATTERIES));
+        assert_eq!(super::parse_batteries(Some("3".into())), Ok(3));
+        assert!(super::parse_batteries(Some("0".into())).is_err());
+        assert!(super::parse_batteries(Some("-1".into())).is_err());
+        assert!(super::parse_batteries(Some("many".into())).is_err());
+    }
+
+    #[test]
+    fn test_too_few_batteries_is_an_error() {
+        let input = b"987654321111111\n8111111\n";
+        let mut reader: &[u8] = &input[..];
+        let result = super::solve(&mut reader, DEFAULT_BATTERIES);
+        assert!(result.is_err());
     }
 }
@@ -0,0 +1,594 @@
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+use std::iter::Sum;
+
+// Each limb holds 18 decimal digits, so a limb always fits into a u64 and adding two limbs
+// plus a carry never overflows.
+const LIMB_DIGITS: usize = 18;
+const LIMB_BASE: u64 = 1_000_000_000_000_000_000;
+
+/// The joltage of the batteries selected from a bank, kept as its decimal digits so that
+/// arbitrarily many batteries can be selected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Joltage {
+    digits: String,
+}
+
+impl Joltage {
+    pub fn digits(&self) -> &str {
+        &self.digits
+    }
+
+    /// Returns the joltage as a number, or `None` if it does not fit into a `u128`.
+    pub fn value(&self) -> Option<u128> {
+        if self.digits.is_empty() {
+            return Some(0);
+        }
+        self.digits.parse().ok()
+    }
+}
+
+impl fmt::Display for Joltage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.digits.is_empty() {
+            write!(f, "0")
+        } else {
+            write!(f, "{}", self.digits)
+        }
+    }
+}
+
+/// An unbounded sum of joltages.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JoltageSum {
+    // Little endian, base LIMB_BASE
+    limbs: Vec<u64>,
+}
+
+impl JoltageSum {
+    pub fn add(&mut self, joltage: &Joltage) {
+        let digits = joltage.digits().as_bytes();
+
+        let mut carry = 0;
+        let mut index = 0;
+        let mut end = digits.len();
+        while end > 0 || carry > 0 {
+            let start = end.saturating_sub(LIMB_DIGITS);
+            let limb = digits[start..end]
+                .iter()
+                .fold(0, |acc, &d| acc * 10 + (d - b'0') as u64);
+            end = start;
+
+            if index == self.limbs.len() {
+                self.limbs.push(0);
+            }
+            let sum = self.limbs[index] + limb + carry;
+            self.limbs[index] = sum % LIMB_BASE;
+            carry = sum / LIMB_BASE;
+            index += 1;
+        }
+    }
+
+    /// Returns the sum as a number, or `None` if it does not fit into a `u128`.
+    pub fn to_u128(&self) -> Option<u128> {
+        self.limbs.iter().rev().try_fold(0u128, |acc, &limb| {
+            acc.checked_mul(LIMB_BASE as u128)?
+                .checked_add(limb as u128)
+        })
+    }
+}
+
+impl fmt::Display for JoltageSum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut limbs = self.limbs.iter().rev().skip_while(|&&limb| limb == 0);
+        match limbs.next() {
+            None => write!(f, "0"),
+            Some(most_significant) => {
+                write!(f, "{}", most_significant)?;
+                for limb in limbs {
+                    write!(f, "{:0width$}", limb, width = LIMB_DIGITS)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Sum<Joltage> for JoltageSum {
+    fn sum<I: Iterator<Item = Joltage>>(iter: I) -> Self {
+        let mut total = JoltageSum::default();
+        for joltage in iter {
+            total.add(&joltage);
+        }
+        total
+    }
+}
+
+/// A bank of batteries, as read from one line of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bank {
+    // ASCII digits
+    digits: Vec<u8>,
+    line: usize,
+}
+
+impl Bank {
+    pub fn len(&self) -> usize {
+        self.digits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.digits.is_empty()
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BankError {
+    InvalidCharacter {
+        line: usize,
+        column: usize,
+        character: char,
+    },
+    TooFewBatteries {
+        line: usize,
+        available: usize,
+        required: usize,
+    },
+    /// The line is not valid UTF-8.
+    InvalidUtf8 { line: usize },
+    /// Reading the line failed.
+    Io { line: usize, kind: io::ErrorKind },
+}
+
+impl BankError {
+    fn from_io(error: io::Error, line: usize) -> BankError {
+        match error.kind() {
+            io::ErrorKind::InvalidData => BankError::InvalidUtf8 { line },
+            kind => BankError::Io { line, kind },
+        }
+    }
+}
+
+impl fmt::Display for BankError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BankError::InvalidCharacter {
+                line,
+                column,
+                character,
+            } => write!(
+                f,
+                "line {}, column {}: invalid battery {:?}, expected a digit",
+                line, column, character
+            ),
+            BankError::TooFewBatteries {
+                line,
+                available,
+                required,
+            } => write!(
+                f,
+                "line {}: bank has {} batteries, but {} must be selected",
+                line, available, required
+            ),
+            BankError::InvalidUtf8 { line } => write!(f, "line {}: not valid UTF-8", line),
+            BankError::Io { line, kind } => {
+                write!(f, "line {}: could not read the input: {}", line, kind)
+            }
+        }
+    }
+}
+
+impl Error for BankError {}
+
+/// Parses a single bank. Surrounding whitespace (including a `\r` from CRLF line endings) is
+/// ignored, every other character must be a digit. Line and column numbers start at 1.
+pub fn parse_bank(line: &str, line_number: usize) -> Result<Bank, BankError> {
+    let leading = line.len() - line.trim_start().len();
+
+    let digits = line
+        .trim()
+        .char_indices()
+        .map(|(index, character)| {
+            if character.is_ascii_digit() {
+                Ok(character as u8)
+            } else {
+                Err(BankError::InvalidCharacter {
+                    line: line_number,
+                    column: line[..leading + index].chars().count() + 1,
+                    character,
+                })
+            }
+        })
+        .collect::<Result<Vec<u8>, BankError>>()?;
+
+    Ok(Bank {
+        digits,
+        line: line_number,
+    })
+}
+
+/// Parses one bank per line, skipping blank lines. A line that cannot be read ends the banks
+/// with an error.
+pub fn parse_banks(reader: &mut dyn BufRead) -> impl Iterator<Item = Result<Bank, BankError>> {
+    let mut failed = false;
+    reader
+        .lines()
+        .enumerate()
+        .map_while(move |(index, line)| {
+            if failed {
+                return None;
+            }
+            failed = line.is_err();
+            Some(
+                line.map_err(|error| BankError::from_io(error, index + 1))
+                    .map(|line| (index + 1, line)),
+            )
+        })
+        .filter(|line| !matches!(line, Ok((_, text)) if text.trim().is_empty()))
+        .map(|line| line.and_then(|(line_number, text)| parse_bank(&text, line_number)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    Max,
+    Min,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Constraint {
+    /// No two selected batteries may be next to each other in the bank.
+    NoAdjacent,
+    /// The bank is split into consecutive blocks of the given size, and at most one battery
+    /// may be selected from each block.
+    AtMostOnePerBlock(usize),
+}
+
+impl Constraint {
+    // The first position that may be selected after selecting `position`
+    fn next_allowed(&self, position: usize) -> usize {
+        match self {
+            Constraint::NoAdjacent => position + 2,
+            Constraint::AtMostOnePerBlock(size) => (position / size + 1) * size,
+        }
+    }
+}
+
+/// Selects `batteries` batteries from the bank, keeping their order, so that the resulting
+/// joltage is optimal for the objective while satisfying all constraints. Returns `None` if
+/// no such selection exists.
+///
+/// Panics if a constraint has a block size of zero.
+pub fn select(
+    bank: &Bank,
+    batteries: usize,
+    objective: Objective,
+    constraints: &[Constraint],
+) -> Option<Joltage> {
+    assert!(
+        !constraints.contains(&Constraint::AtMostOnePerBlock(0)),
+        "block size must be positive"
+    );
+
+    let digits = &bank.digits;
+    let len = digits.len();
+
+    let next_allowed = |position: usize| {
+        constraints
+            .iter()
+            .map(|constraint| constraint.next_allowed(position))
+            .fold(position + 1, usize::max)
+            .min(len)
+    };
+
+    // most_selectable[p] is the largest number of batteries that can be selected from p onwards
+    let mut most_selectable = vec![0; len + 1];
+    for position in (0..len).rev() {
+        most_selectable[position] =
+            most_selectable[position + 1].max(1 + most_selectable[next_allowed(position)]);
+    }
+    if most_selectable[0] < batteries {
+        return None;
+    }
+
+    // next_occurrence[d][p] is the first position at or after p holding digit d
+    let mut next_occurrence = vec![vec![len; len + 1]; 10];
+    for position in (0..len).rev() {
+        for (digit, occurrences) in next_occurrence.iter_mut().enumerate() {
+            occurrences[position] = if (digits[position] - b'0') as usize == digit {
+                position
+            } else {
+                occurrences[position + 1]
+            };
+        }
+    }
+
+    let preference: Vec<usize> = match objective {
+        Objective::Max => (0..10).rev().collect(),
+        Objective::Min => (0..10).collect(),
+    };
+
+    // Pick the best digit that still leaves enough batteries for the rest of the selection.
+    // Taking its earliest occurrence keeps the most options open for the following digits.
+    let mut selected = Vec::with_capacity(batteries);
+    let mut start = 0;
+    for remaining in (1..=batteries).rev() {
+        let position = preference
+            .iter()
+            .map(|&digit| next_occurrence[digit][start])
+            .find(|&position| {
+                position < len && 1 + most_selectable[next_allowed(position)] >= remaining
+            })
+            .expect("a feasible position exists while enough batteries are selectable");
+
+        selected.push(digits[position]);
+        start = next_allowed(position);
+    }
+
+    Some(Joltage {
+        digits: String::from_utf8(selected).expect("only ASCII digits are selected"),
+    })
+}
+
+/// Selects `batteries` batteries from the bank, keeping their order, so that the resulting
+/// joltage is as large as possible.
+pub fn max_joltage_of_bank(bank: &Bank, batteries: usize) -> Result<Joltage, BankError> {
+    select(bank, batteries, Objective::Max, &[]).ok_or(BankError::TooFewBatteries {
+        line: bank.line,
+        available: bank.len(),
+        required: batteries,
+    })
+}
+
+/// Sums the largest joltages of all banks, failing on the first invalid bank.
+pub fn total_max_joltage(
+    reader: &mut dyn BufRead,
+    batteries: usize,
+) -> Result<JoltageSum, BankError> {
+    parse_banks(reader)
+        .map(|bank| max_joltage_of_bank(&bank?, batteries))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::XorShift;
+    use rstest::rstest;
+
+    fn bank(line: &str) -> Bank {
+        parse_bank(line, 1).unwrap()
+    }
+
+    #[rstest]
+    #[case("987654321111111\n", 987654321111)]
+    #[case("811111111111119\n", 811111111119)]
+    #[case("234234234234278\n", 434234234278)]
+    #[case("818181911112111\n", 888911112111)]
+    fn test_max_joltage_of_bank(#[case] input: &str, #[case] expected: u128) {
+        let result = max_joltage_of_bank(&bank(input), 12).unwrap();
+        assert_eq!(result.value(), Some(expected));
+    }
+
+    #[rstest]
+    #[case("987654321111111", 98)]
+    #[case("811111111111119", 89)]
+    #[case("234234234234278", 78)]
+    #[case("818181911112111", 92)]
+    fn test_max_joltage_of_bank_two_batteries(#[case] input: &str, #[case] expected: u128) {
+        let result = max_joltage_of_bank(&bank(input), 2).unwrap();
+        assert_eq!(result.value(), Some(expected));
+    }
+
+    // Tries every possible selection, only usable for tiny banks
+    fn brute_force(
+        bank: &str,
+        batteries: usize,
+        objective: Objective,
+        constraints: &[Constraint],
+    ) -> Option<String> {
+        let digits = bank.as_bytes();
+        let is_valid = |positions: &[usize]| {
+            positions.windows(2).all(|pair| {
+                constraints
+                    .iter()
+                    .all(|constraint| pair[1] >= constraint.next_allowed(pair[0]))
+            })
+        };
+
+        (0u32..1 << digits.len())
+            .filter(|mask| mask.count_ones() as usize == batteries)
+            .map(|mask| {
+                (0..digits.len())
+                    .filter(|i| mask & (1 << i) != 0)
+                    .collect::<Vec<usize>>()
+            })
+            .filter(|positions| is_valid(positions))
+            .map(|positions| positions.iter().map(|&i| digits[i] as char).collect())
+            .reduce(|best: String, candidate| {
+                let better = match objective {
+                    Objective::Max => candidate > best,
+                    Objective::Min => candidate < best,
+                };
+                if better { candidate } else { best }
+            })
+    }
+
+    #[test]
+    fn test_select_matches_brute_force() {
+        let constraint_sets: [&[Constraint]; 5] = [
+            &[],
+            &[Constraint::NoAdjacent],
+            &[Constraint::AtMostOnePerBlock(2)],
+            &[Constraint::AtMostOnePerBlock(3)],
+            &[Constraint::NoAdjacent, Constraint::AtMostOnePerBlock(3)],
+        ];
+
+        let mut random = XorShift::new(0x2545f491);
+        let mut next_digit = || (b'0' + (random.next_u32() % 10) as u8) as char;
+
+        for len in 0..=10 {
+            for _ in 0..20 {
+                let line: String = (0..len).map(|_| next_digit()).collect();
+                for constraints in constraint_sets {
+                    for objective in [Objective::Max, Objective::Min] {
+                        for batteries in 0..=len {
+                            let expected = brute_force(&line, batteries, objective, constraints);
+                            let result = select(&bank(&line), batteries, objective, constraints)
+                                .map(|joltage| joltage.digits().to_string());
+                            assert_eq!(
+                                result, expected,
+                                "bank {line}, {batteries} batteries, {objective:?}, {constraints:?}"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[rstest]
+    #[case("987654321111111", 2, Objective::Min, &[], Some("11"))]
+    #[case("234234234234278", 3, Objective::Min, &[], Some("222"))]
+    #[case("987654321111111", 3, Objective::Max, &[Constraint::NoAdjacent], Some("975"))]
+    #[case("811111111111119", 2, Objective::Max, &[Constraint::AtMostOnePerBlock(5)], Some("89"))]
+    #[case("12345", 3, Objective::Max, &[Constraint::NoAdjacent], Some("135"))]
+    #[case("12345", 4, Objective::Max, &[Constraint::NoAdjacent], None)]
+    #[case("123456", 3, Objective::Min, &[Constraint::AtMostOnePerBlock(2)], Some("135"))]
+    fn test_select(
+        #[case] line: &str,
+        #[case] batteries: usize,
+        #[case] objective: Objective,
+        #[case] constraints: &[Constraint],
+        #[case] expected: Option<&str>,
+    ) {
+        let result = select(&bank(line), batteries, objective, constraints);
+        assert_eq!(result.as_ref().map(Joltage::digits), expected);
+    }
+
+    #[test]
+    fn test_max_joltage_of_bank_selects_whole_bank() {
+        let line = "9".repeat(50);
+        let result = max_joltage_of_bank(&bank(&line), 50).unwrap();
+        assert_eq!(result.digits(), line);
+        assert_eq!(result.value(), None);
+    }
+
+    #[test]
+    fn test_joltage_sum_carries_across_limbs() {
+        let mut sum = JoltageSum::default();
+        sum.add(&max_joltage_of_bank(&bank(&"9".repeat(40)), 40).unwrap());
+        sum.add(&max_joltage_of_bank(&bank("1"), 1).unwrap());
+        assert_eq!(sum.to_string(), format!("1{}", "0".repeat(40)));
+        assert_eq!(sum.to_u128(), None);
+    }
+
+    #[test]
+    fn test_joltage_sum_to_u128() {
+        let sum: JoltageSum = ["987654321111", "811111111119"]
+            .iter()
+            .map(|line| max_joltage_of_bank(&bank(line), 12).unwrap())
+            .sum();
+        assert_eq!(sum.to_u128(), Some(987654321111 + 811111111119));
+        assert_eq!(sum.to_string(), "1798765432230");
+    }
+
+    #[test]
+    fn test_empty_joltage_sum_is_zero() {
+        let sum = JoltageSum::default();
+        assert_eq!(sum.to_string(), "0");
+        assert_eq!(sum.to_u128(), Some(0));
+    }
+
+    #[rstest]
+    #[case("12a4", 3, 'a')]
+    #[case("  12 4", 5, ' ')]
+    #[case("1é2", 2, 'é')]
+    #[case("\u{a0}12-4", 4, '-')]
+    fn test_parse_bank_invalid_character(
+        #[case] line: &str,
+        #[case] column: usize,
+        #[case] character: char,
+    ) {
+        let result = parse_bank(line, 5);
+        assert_eq!(
+            result,
+            Err(BankError::InvalidCharacter {
+                line: 5,
+                column,
+                character,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_bank_ignores_surrounding_whitespace() {
+        let result = parse_bank("  1234\r", 7).unwrap();
+        assert_eq!(result.len(), 4);
+        assert_eq!(result.line(), 7);
+    }
+
+    #[test]
+    fn test_max_joltage_of_bank_too_few_batteries() {
+        let result = max_joltage_of_bank(&parse_bank("12345", 3).unwrap(), 12);
+        assert_eq!(
+            result,
+            Err(BankError::TooFewBatteries {
+                line: 3,
+                available: 5,
+                required: 12,
+            })
+        );
+    }
+
+    #[test]
+    fn test_total_max_joltage_reports_line_of_invalid_bank() {
+        let mut reader: &[u8] = b"987654321111111\n\n81111111x111119\n";
+        let result = total_max_joltage(&mut reader, 2);
+        assert_eq!(
+            result,
+            Err(BankError::InvalidCharacter {
+                line: 3,
+                column: 9,
+                character: 'x',
+            })
+        );
+    }
+
+    #[test]
+    fn test_total_max_joltage_reports_invalid_utf8() {
+        let mut reader: &[u8] = b"91\n\xe9\n99\n";
+        let result = total_max_joltage(&mut reader, 2);
+        assert_eq!(result, Err(BankError::InvalidUtf8 { line: 2 }));
+    }
+
+    #[test]
+    fn test_parse_banks_stops_after_read_error() {
+        struct Failing;
+        impl io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("disk on fire"))
+            }
+        }
+        let mut reader = io::BufReader::new(Failing);
+        let banks: Vec<_> = parse_banks(&mut reader).collect();
+        assert_eq!(
+            banks,
+            vec![Err(BankError::Io {
+                line: 1,
+                kind: io::ErrorKind::Other
+            })]
+        );
+    }
+
+    #[test]
+    fn test_total_max_joltage_skips_blank_lines() {
+        let mut reader: &[u8] = b"987654321111111\r\n\r\n811111111111119\r\n\n";
+        let result = total_max_joltage(&mut reader, 2).unwrap();
+        assert_eq!(result.to_u128(), Some(98 + 89));
+    }
+}
@@ -1,94 +1,46 @@
+use aoc2025::Part;
+use aoc2025::day4::{self, Backend, InputError, Rule};
 use std::io::BufRead;
 
 fn main() {
-    let mut handle = std::io::stdin().lock();
-    let result = solve(&mut handle);
-
-    println!("{}", result);
-}
-
-struct Grid {
-    cells: Vec<char>,
-    rows: usize,
-    cols: usize,
-}
-
-fn solve(reader: &mut dyn BufRead) -> i32 {
-    let grid = parse_grid(reader);
-
-    let mut total_neighbors = 0;
-    for row in 0..grid.rows {
-        for col in 0..grid.cols {
-            if let Some(count) = grid.get_neighbors(row, col) && count < 4 {
-                total_neighbors += 1;
-            }
+    let options = match day4::Options::parse(Part::One, std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, day4::USAGE);
+            std::process::exit(2);
         }
-    }
-
-    total_neighbors
-}
+    };
 
-fn parse_grid(reader: &mut dyn BufRead) -> Grid {
-    let lines: Vec<String> = reader
-        .lines()
-        .filter_map(|line| line.ok())
-        .collect();
-
-    let grid: Vec<char> = lines
-        .iter()
-        .flat_map(|line| line.chars().collect::<Vec<char>>())
-        .collect();
-
-    Grid {
-        cells: grid,
-        rows: lines.len(),
-        cols: lines[0].len(),
+    let mut handle = std::io::stdin().lock();
+    let result = if options.layered {
+        day4::read_space(&mut handle, options.rule).map(|space| space.count_accessible())
+    } else if options.coordinates {
+        day4::read_coordinates(&mut handle, options.rule).map(|grid| grid.count_accessible())
+    } else {
+        solve(&mut handle, &options.rule, options.threads).map(|result| result as usize)
+    };
+
+    match result {
+        Ok(result) => println!("{}", result),
+        Err(error) => error.exit(),
     }
 }
 
-impl Grid {
-    fn get_neighbors(&self, row: usize, col: usize) -> Option<i32> {
-        let directions: [(isize,isize);8] = [
-            (-1, -1),
-            (0, -1),
-            (1, -1),
-            (-1, 0),
-            (1, 0),
-            (-1, 1),
-            (0, 1),
-            (1, 1),
-        ];
+fn solve(reader: &mut dyn BufRead, rule: &Rule, threads: usize) -> Result<i32, InputError> {
+    let grid = day4::read_grid(reader, rule.clone())?.with_threads(threads);
 
-        match self.cells.get(row * self.cols + col) {
-            None => None,
-            Some('.') => None,
-            _ => {
-                let mut neighbors = 0;
-                for (dc, dr) in directions.iter() {
-                    let new_row = row as isize + dr;
-                    let new_col = col as isize + dc;
-                    if new_row >= 0 && new_row < self.rows as isize
-                        && new_col >= 0 && new_col < self.cols as isize
-                        && self.cells[new_row as usize * self.cols + new_col as usize] == '@' {
-                        neighbors += 1;
-                    }
-                }
-
-                Some(neighbors)
-            },
-        }
-    }
+    Ok(grid.count_accessible() as i32)
 }
 
 #[cfg(test)]
 mod tests {
-    use rstest::rstest;
+    use aoc2025::day4::{Comparison, InputError, Neighborhood, Rule};
 
     #[test]
     fn test_sample() {
         let input = "..@@.@@@@.\n@@@.@.@.@@\n@@@@@.@.@@\n@.@@@@..@.\n@@.@@@@.@@\n.@@@@@@@.@\n.@.@.@.@@@\n@.@@@.@@@@\n.@@@@@@@@.\n@.@.@@@.@.";
         let mut reader = std::io::Cursor::new(input);
-        let result = super::solve(&mut reader);
+        let result = super::solve(&mut reader, &Rule::default(), 1).unwrap();
         assert_eq!(result, 13);
     }
 
@@ -96,32 +48,40 @@ mod tests {
     fn test_input_from_file() {
         let input = include_str!("input.txt");
         let mut reader = input.as_bytes();
-        let result = super::solve(&mut reader);
+        let result = super::solve(&mut reader, &Rule::default(), 1).unwrap();
         assert_eq!(result, 1457);
     }
 
     #[test]
-    fn test_parse_grid() {
-        let input = "..@.\n@@..\n.@.@\n";
-        let mut reader = std::io::Cursor::new(input);
-        let grid = super::parse_grid(&mut reader);
-        assert_eq!(grid.rows, 3);
-        assert_eq!(grid.cols, 4);
-        assert_eq!(grid.cells, vec!['.', '.', '@', '.', '@', '@', '.', '.', '.', '@', '.', '@']);
+    fn test_input_from_file_with_threads() {
+        let input = include_str!("input.txt");
+        let mut reader = input.as_bytes();
+        let result = super::solve(&mut reader, &Rule::default(), 4).unwrap();
+        assert_eq!(result, 1457);
     }
 
-    #[rstest]
-    #[case(b"..@.\n@@..\n.@.@\n", 2, 0, None)] // cell is '.', has no neighbors
-    #[case(b"..@.\n@@..\n.@.@\n", 2, 1, Some(2))]
-    fn test_get_neighbors(
-        #[case] input: &[u8],
-        #[case] row: usize,
-        #[case] col: usize,
-        #[case] expected: Option<i32>,
-    ) {
+    #[test]
+    fn test_sample_fewer_than_three_orthogonal_neighbors() {
+        let input = "..@@.@@@@.\n@@@.@.@.@@\n@@@@@.@.@@\n@.@@@@..@.\n@@.@@@@.@@\n.@@@@@@@.@\n.@.@.@.@@@\n@.@@@.@@@@\n.@@@@@@@@.\n@.@.@@@.@.";
         let mut reader = std::io::Cursor::new(input);
-        let grid = super::parse_grid(&mut reader);
-        let neighbors = grid.get_neighbors(row, col);
-        assert_eq!(neighbors, expected);
+        let rule = Rule {
+            neighborhood: Neighborhood::VonNeumann,
+            comparison: Comparison::Less,
+            threshold: 3,
+            ..Rule::default()
+        };
+        let result = super::solve(&mut reader, &rule, 1).unwrap();
+        assert_eq!(result, 37);
+    }
+
+    #[test]
+    fn test_invalid_rule_is_an_error() {
+        let mut reader = std::io::Cursor::new("@@\n@@\n");
+        let rule = Rule {
+            neighborhood: Neighborhood::Radius(8),
+            ..Rule::default()
+        };
+        let result = super::solve(&mut reader, &rule, 1);
+        assert!(matches!(result, Err(InputError::Rule(_))));
     }
-}
\ No newline at end of file
+}
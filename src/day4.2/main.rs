@@ -1,135 +1,133 @@
-use std::io::BufRead;
-use std::time::Instant;
+use aoc2025::Part;
+use aoc2025::day4::{
+    self, Backend, Grid, InputError, RemovalHistory, Renderer, Rule, Tui, TuiError,
+};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::time::{Duration, Instant};
 
 fn main() {
-    let mut handle = std::io::stdin().lock();
-
-    let now = Instant::now();
-    let result = solve(&mut handle);
-    let elapsed = now.elapsed();
+    let options = match day4::Options::parse(Part::Two, std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, day4::USAGE);
+            std::process::exit(2);
+        }
+    };
 
-    println!("{}", result);
-    eprintln!("Elapsed: {:.2?}", elapsed);
-}
+    let mut handle = std::io::stdin().lock();
 
-struct Grid {
-    cells: Vec<char>,
-    rolls: Vec<(usize, usize)>,
-    rows: usize,
-    cols: usize,
-}
+    if options.layered {
+        let rounds = day4::read_space(&mut handle, options.rule)
+            .unwrap_or_else(|error| error.exit())
+            .remove_all();
+        println!("{}", rounds.iter().sum::<usize>());
+        return;
+    }
 
-fn solve(reader: &mut dyn BufRead) -> i32 {
-    let mut grid = parse_grid(reader);
+    if options.coordinates {
+        let mut grid =
+            day4::read_coordinates(&mut handle, options.rule).unwrap_or_else(|error| error.exit());
+        println!("{}", grid.remove_all().iter().sum::<usize>());
+        return;
+    }
 
-    let mut total_removed = 0;
-    loop {
-        let removed = grid.remove();
-        if removed == 0 {
-            break total_removed;
+    if options.tui {
+        let mut grid = read_grid(&mut handle, options.rule, options.threads);
+        match animate(&mut grid, options.delay) {
+            Ok(history) => println!("{}", history.total_removed()),
+            Err(TuiError::Quit) => {}
+            Err(TuiError::Io(error)) => {
+                eprintln!("Could not animate the grid: {}", error);
+                std::process::exit(1);
+            }
         }
-        total_removed += removed;
+        return;
     }
-}
 
-fn parse_grid(reader: &mut dyn BufRead) -> Grid {
-    let lines: Vec<String> = reader.lines().filter_map(|line| line.ok()).collect();
-
-    let grid: Vec<char> = lines
-        .iter()
-        .flat_map(|line| line.chars().collect::<Vec<char>>())
-        .collect();
-
-    let rows = lines.len();
-    let cols = lines[0].len();
-    let mut rolls = Vec::new();
+    if options.history || options.render.is_some() {
+        let mut grid = read_grid(&mut handle, options.rule, options.threads);
+
+        let result = match options.render {
+            Some(dir) => render(&mut grid, Renderer::new(dir, options.format, options.scale)),
+            None => Ok(grid.remove_all_recorded()),
+        };
+        let history = match result {
+            Ok(history) => history,
+            Err(error) => {
+                eprintln!("Could not render the grid: {}", error);
+                std::process::exit(1);
+            }
+        };
 
-    for row in 0..rows {
-        for col in 0..cols {
-            let idx = row * cols + col;
-            if grid[idx] == '@' {
-                rolls.push((row, col));
+        println!("{}", history.total_removed());
+        if options.history {
+            for (round, removed) in history.rounds().iter().enumerate() {
+                println!("Round {}: {} removed", round + 1, removed);
             }
+            print!("{}", history);
         }
+        return;
     }
 
-    Grid {
-        cells: grid,
-        rolls,
-        rows,
-        cols,
+    let now = Instant::now();
+    let result = solve(&mut handle, &options.rule, options.threads);
+    let elapsed = now.elapsed();
+
+    match result {
+        Ok(result) => println!("{}", result),
+        Err(error) => error.exit(),
     }
+    eprintln!("Elapsed: {:.2?}", elapsed);
 }
 
-impl Grid {
-    fn get_neighbors(&self, row: usize, col: usize) -> Option<i32> {
-        let directions: [(isize, isize); 8] = [
-            (-1, -1),
-            (0, -1),
-            (1, -1),
-            (-1, 0),
-            (1, 0),
-            (-1, 1),
-            (0, 1),
-            (1, 1),
-        ];
-
-        if row >= self.rows || col >= self.cols {
-            return None;
-        }
+fn read_grid(reader: &mut dyn BufRead, rule: Rule, threads: usize) -> Grid {
+    day4::read_grid(reader, rule)
+        .unwrap_or_else(|error| error.exit())
+        .with_threads(threads)
+}
 
-        if self.cells[row * self.cols + col] == '.' {
-            return None;
-        }
+fn render(grid: &mut Grid, mut renderer: Renderer) -> io::Result<RemovalHistory> {
+    renderer.write_frame(grid, &[])?;
+    grid.remove_all_with(|grid, removed| renderer.write_frame(grid, removed))
+}
 
-        let mut neighbors = 0;
-        for (dc, dr) in directions.iter() {
-            let new_row = row as isize + dr;
-            let new_col = col as isize + dc;
-            if new_row >= 0
-                && new_row < self.rows as isize
-                && new_col >= 0
-                && new_col < self.cols as isize
-                && self.cells[new_row as usize * self.cols + new_col as usize] == '@'
-            {
-                neighbors += 1;
-            }
-        }
+// The grid is read from stdin, so the animation is controlled through the terminal instead
+fn animate(grid: &mut Grid, delay: Duration) -> Result<RemovalHistory, TuiError> {
+    let commands = match File::open("/dev/tty") {
+        Ok(tty) => day4::spawn_command_reader(BufReader::new(tty)),
+        Err(_) => day4::spawn_command_reader(io::empty()),
+    };
+    let mut tui = Tui::new(io::stdout().lock(), commands, delay);
+
+    tui.start(grid)?;
+    let history = grid.remove_all_with(|grid, removed| tui.show_round(grid, removed))?;
+    tui.finish(grid)?;
+    Ok(history)
+}
 
-        Some(neighbors)
-    }
+fn solve(reader: &mut dyn BufRead, rule: &Rule, threads: usize) -> Result<i32, InputError> {
+    let mut grid = day4::read_grid(reader, rule.clone())?.with_threads(threads);
 
-    fn remove(&mut self) -> i32 {
-        let mut removed = 0;
-
-        let mut next_rolls = Vec::new();
-        for (row, col) in self.rolls.iter() {
-            if let Some(neighbors) = self.get_neighbors(*row, *col) {
-                if neighbors < 4 {
-                    if let Some(cell) = self.cells.get_mut(row * self.cols + col) {
-                        *cell = '.';
-                        removed += 1;
-                    }
-                } else {
-                    next_rolls.push((*row, *col));
-                }
-            }
+    let mut total_removed = 0;
+    loop {
+        let removed = grid.remove();
+        if removed == 0 {
+            break Ok(total_removed);
         }
-        self.rolls = next_rolls;
-
-        removed
+        total_removed += removed;
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use rstest::rstest;
+    use aoc2025::day4::{InputError, Neighborhood, Rule};
 
     #[test]
     fn test_sample() {
         let input = "..@@.@@@@.\n@@@.@.@.@@\n@@@@@.@.@@\n@.@@@@..@.\n@@.@@@@.@@\n.@@@@@@@.@\n.@.@.@.@@@\n@.@@@.@@@@\n.@@@@@@@@.\n@.@.@@@.@.";
         let mut reader = std::io::Cursor::new(input);
-        let result = super::solve(&mut reader);
+        let result = super::solve(&mut reader, &Rule::default(), 1).unwrap();
         assert_eq!(result, 43);
     }
 
@@ -137,35 +135,26 @@ mod tests {
     fn test_input_from_file() {
         let input = include_str!("input.txt");
         let mut reader = input.as_bytes();
-        let result = super::solve(&mut reader);
+        let result = super::solve(&mut reader, &Rule::default(), 1).unwrap();
         assert_eq!(result, 8310);
     }
 
     #[test]
-    fn test_parse_grid() {
-        let input = "..@.\n@@..\n.@.@\n";
-        let mut reader = std::io::Cursor::new(input);
-        let grid = super::parse_grid(&mut reader);
-        assert_eq!(grid.rows, 3);
-        assert_eq!(grid.cols, 4);
-        assert_eq!(
-            grid.cells,
-            vec!['.', '.', '@', '.', '@', '@', '.', '.', '.', '@', '.', '@']
-        );
+    fn test_input_from_file_with_threads() {
+        let input = include_str!("input.txt");
+        let mut reader = input.as_bytes();
+        let result = super::solve(&mut reader, &Rule::default(), 4).unwrap();
+        assert_eq!(result, 8310);
     }
 
-    #[rstest]
-    #[case(b"..@.\n@@..\n.@.@\n", 2, 0, None)] // cell is '.', has no neighbors
-    #[case(b"..@.\n@@..\n.@.@\n", 2, 1, Some(2))]
-    fn test_get_neighbors(
-        #[case] input: &[u8],
-        #[case] row: usize,
-        #[case] col: usize,
-        #[case] expected: Option<i32>,
-    ) {
-        let mut reader = std::io::Cursor::new(input);
-        let grid = super::parse_grid(&mut reader);
-        let neighbors = grid.get_neighbors(row, col);
-        assert_eq!(neighbors, expected);
+    #[test]
+    fn test_invalid_rule_is_an_error() {
+        let mut reader = std::io::Cursor::new("@@\n@@\n");
+        let rule = Rule {
+            neighborhood: Neighborhood::Custom((1..=300).map(|col| (0, col)).collect()),
+            ..Rule::default()
+        };
+        let result = super::solve(&mut reader, &rule, 1);
+        assert!(matches!(result, Err(InputError::Rule(_))));
     }
 }
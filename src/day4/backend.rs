@@ -0,0 +1,28 @@
+use super::rule::Rule;
+
+/// The operations every way of storing the rolls supports, so callers can swap one backend
+/// for another.
+pub trait Backend {
+    /// Identifies a cell, `(row, col)` in a flat grid and one coordinate per axis in a space.
+    type Cell;
+
+    fn rule(&self) -> &Rule;
+
+    fn count_rolls(&self) -> usize;
+
+    /// Returns the rolls that can be reached by a forklift right now, in reading order.
+    fn accessible(&self) -> Vec<Self::Cell>;
+
+    /// Returns how many rolls can be reached by a forklift right now.
+    fn count_accessible(&self) -> usize;
+
+    /// Removes all rolls that are accessible at the start of the round and returns how many
+    /// were removed.
+    fn remove(&mut self) -> i32;
+
+    /// Keeps removing rolls until none are accessible anymore and returns how many were
+    /// removed in each round.
+    fn remove_all(&mut self) -> Vec<usize> {
+        std::iter::from_fn(|| Some(self.remove() as usize).filter(|&removed| removed > 0)).collect()
+    }
+}
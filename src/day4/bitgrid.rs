@@ -0,0 +1,422 @@
+use super::edge::EdgeMode;
+use super::removal::bands;
+use super::rule::{Comparison, MAX_NEIGHBORHOOD_SIZE, Rule};
+use std::fmt;
+use std::ops::Range;
+use std::thread;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+// Enough bit planes to count every cell of the largest supported neighbourhood
+const COUNT_PLANES: usize = (usize::BITS - MAX_NEIGHBORHOOD_SIZE.leading_zeros()) as usize;
+
+/// A grid of rolls with one bit per cell. Every row starts at a word boundary and the unused
+/// bits at the end of a row are always zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitGrid {
+    words: Vec<u64>,
+    rows: usize,
+    cols: usize,
+    words_per_row: usize,
+}
+
+impl BitGrid {
+    pub fn new(rows: usize, cols: usize) -> BitGrid {
+        let words_per_row = cols.div_ceil(WORD_BITS);
+        BitGrid {
+            words: vec![0; rows * words_per_row],
+            rows,
+            cols,
+            words_per_row,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Appends a row, cells beyond the grid width are ignored.
+    pub fn push_row(&mut self, cells: impl IntoIterator<Item = bool>) {
+        let start = self.words.len();
+        self.words.resize(start + self.words_per_row, 0);
+        for (col, _) in cells
+            .into_iter()
+            .take(self.cols)
+            .enumerate()
+            .filter(|(_, cell)| *cell)
+        {
+            self.words[start + col / WORD_BITS] |= 1 << (col % WORD_BITS);
+        }
+        self.rows += 1;
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> bool {
+        row < self.rows
+            && col < self.cols
+            && self.words[row * self.words_per_row + col / WORD_BITS] >> (col % WORD_BITS) & 1 == 1
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, value: bool) {
+        assert!(row < self.rows && col < self.cols, "cell out of bounds");
+        let word = &mut self.words[row * self.words_per_row + col / WORD_BITS];
+        if value {
+            *word |= 1 << (col % WORD_BITS);
+        } else {
+            *word &= !(1 << (col % WORD_BITS));
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Iterates over the positions of all set cells, row by row.
+    pub fn iter_ones(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.rows).flat_map(move |row| {
+            self.row_words(row)
+                .iter()
+                .enumerate()
+                .flat_map(move |(word_index, &word)| {
+                    set_bits(word).map(move |bit| (row, word_index * WORD_BITS + bit))
+                })
+        })
+    }
+
+    fn row_words(&self, row: usize) -> &[u64] {
+        &self.words[row * self.words_per_row..(row + 1) * self.words_per_row]
+    }
+
+    /// Counts the neighbouring rolls of the 64 cells in one word of a row at once, for the
+    /// given `(row, col)` offsets. Bit `i` of `planes[k]` is bit `k` of the neighbour count of
+    /// column `64 * word + i`.
+    pub fn neighbor_count_planes(
+        &self,
+        row: usize,
+        word: usize,
+        offsets: &[(isize, isize)],
+        edges: EdgeMode,
+    ) -> [u64; COUNT_PLANES] {
+        let mut planes = [0; COUNT_PLANES];
+
+        for &(dr, dc) in offsets {
+            let plane = match edges.resolve(row as isize + dr, self.rows) {
+                Some(neighbor_row) => self.shifted_row_word(neighbor_row, word, dc, edges),
+                None if edges == EdgeMode::Roll => !0,
+                None => continue,
+            };
+            add_plane(&mut planes, plane);
+        }
+
+        planes
+    }
+
+    // Bit i of the result is the cell `offset` columns away from column `64 * word + i`
+    fn shifted_row_word(&self, row: usize, word: usize, offset: isize, edges: EdgeMode) -> u64 {
+        let words = self.row_words(row);
+        let mut shifted = shifted_word(words, word, offset);
+
+        // Cells beyond the left or right edge are zero so far, so only words at the edges of
+        // the grid need fixing up when those cells are not empty
+        let first_col = (word * WORD_BITS) as isize + offset;
+        let last_col = first_col + WORD_BITS as isize - 1;
+        if edges != EdgeMode::Empty && (first_col < 0 || last_col >= self.cols as isize) {
+            for bit in 0..WORD_BITS.min(self.cols - word * WORD_BITS) {
+                let col = first_col + bit as isize;
+                if col >= 0 && col < self.cols as isize {
+                    continue;
+                }
+                let outside = match edges.resolve(col, self.cols) {
+                    Some(col) => self.get(row, col),
+                    None => edges == EdgeMode::Roll,
+                };
+                shifted |= (outside as u64) << bit;
+            }
+        }
+
+        shifted
+    }
+
+    /// Iterates over all rolls together with their number of neighbouring rolls.
+    pub fn neighbor_counts<'a>(
+        &'a self,
+        offsets: &'a [(isize, isize)],
+        edges: EdgeMode,
+    ) -> impl Iterator<Item = ((usize, usize), u32)> + 'a {
+        self.neighbor_counts_in(0..self.rows, offsets, edges)
+    }
+
+    /// Like [`BitGrid::neighbor_counts`], but only for the rolls in the given rows.
+    pub fn neighbor_counts_in<'a>(
+        &'a self,
+        rows: Range<usize>,
+        offsets: &'a [(isize, isize)],
+        edges: EdgeMode,
+    ) -> impl Iterator<Item = ((usize, usize), u32)> + 'a {
+        rows.flat_map(move |row| {
+            (0..self.words_per_row).flat_map(move |word| {
+                let rolls = self.row_words(row)[word];
+                let planes = if rolls == 0 {
+                    [0; COUNT_PLANES]
+                } else {
+                    self.neighbor_count_planes(row, word, offsets, edges)
+                };
+                set_bits(rolls)
+                    .map(move |bit| ((row, word * WORD_BITS + bit), count_at(&planes, bit)))
+            })
+        })
+    }
+
+    /// Returns the rolls that are accessible according to the rule.
+    pub fn accessible(&self, rule: &Rule) -> BitGrid {
+        self.accessible_with_threads(rule, 1)
+    }
+
+    /// Like [`BitGrid::accessible`], but splits the grid into bands of rows that are evaluated
+    /// on separate threads. Each band reads the rows around it that are within reach of the
+    /// neighbourhood straight from the shared grid.
+    pub fn accessible_with_threads(&self, rule: &Rule, threads: usize) -> BitGrid {
+        let offsets = rule.neighborhood.offsets();
+        let mut result = BitGrid::new(self.rows, self.cols);
+
+        let bands = bands(self.rows, threads);
+        if bands.len() <= 1 {
+            self.fill_accessible(0..self.rows, &offsets, rule, &mut result.words);
+            return result;
+        }
+
+        thread::scope(|scope| {
+            let mut words = result.words.as_mut_slice();
+            for band in bands {
+                let (band_words, rest) = words.split_at_mut(band.len() * self.words_per_row);
+                words = rest;
+                let offsets = &offsets;
+                scope.spawn(move || self.fill_accessible(band, offsets, rule, band_words));
+            }
+        });
+        result
+    }
+
+    // Writes the accessible rolls of the given rows into `words`, which starts at the first
+    // of those rows
+    fn fill_accessible(
+        &self,
+        rows: Range<usize>,
+        offsets: &[(isize, isize)],
+        rule: &Rule,
+        words: &mut [u64],
+    ) {
+        let first_row = rows.start;
+        for row in rows {
+            for word in 0..self.words_per_row {
+                let rolls = self.row_words(row)[word];
+                if rolls == 0 {
+                    continue;
+                }
+                let planes = self.neighbor_count_planes(row, word, offsets, rule.edges);
+                words[(row - first_row) * self.words_per_row + word] =
+                    rolls & compare(&planes, rule.comparison, rule.threshold);
+            }
+        }
+    }
+}
+
+impl fmt::Display for BitGrid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                write!(f, "{}", if self.get(row, col) { '@' } else { '.' })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+// Iterates over the indices of the set bits of a word, lowest first
+fn set_bits(mut word: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if word == 0 {
+            return None;
+        }
+        let bit = word.trailing_zeros() as usize;
+        word &= word - 1;
+        Some(bit)
+    })
+}
+
+// Reads the count of a single cell back out of the bit planes
+fn count_at(planes: &[u64], bit: usize) -> u32 {
+    planes
+        .iter()
+        .enumerate()
+        .map(|(k, plane)| ((plane >> bit & 1) as u32) << k)
+        .sum()
+}
+
+// Bit i of the result is the cell `offset` columns away from bit i
+fn shifted_word(words: &[u64], word: usize, offset: isize) -> u64 {
+    let word_at = |index: isize| {
+        if index >= 0 && (index as usize) < words.len() {
+            words[index as usize]
+        } else {
+            0
+        }
+    };
+
+    let first_col = (word * WORD_BITS) as isize + offset;
+    let index = first_col.div_euclid(WORD_BITS as isize);
+    let shift = first_col.rem_euclid(WORD_BITS as isize) as u32;
+    if shift == 0 {
+        word_at(index)
+    } else {
+        word_at(index) >> shift | word_at(index + 1) << (WORD_BITS as u32 - shift)
+    }
+}
+
+// Adds a one bit value to every lane of the bit sliced counter, rippling the carry upwards
+fn add_plane(planes: &mut [u64], mut value: u64) {
+    for plane in planes.iter_mut() {
+        let carry = *plane & value;
+        *plane ^= value;
+        value = carry;
+        if value == 0 {
+            break;
+        }
+    }
+}
+
+// Compares every lane of the bit sliced counter against the threshold, starting at the most
+// significant plane
+fn compare(planes: &[u64], comparison: Comparison, threshold: u32) -> u64 {
+    let (less, equal) = if threshold >= 1 << planes.len() {
+        (!0, 0)
+    } else {
+        let mut less = 0;
+        let mut equal = !0;
+        for (bit, &plane) in planes.iter().enumerate().rev() {
+            if threshold >> bit & 1 == 1 {
+                less |= equal & !plane;
+                equal &= plane;
+            } else {
+                equal &= !plane;
+            }
+        }
+        (less, equal)
+    };
+
+    match comparison {
+        Comparison::Less => less,
+        Comparison::LessOrEqual => less | equal,
+        Comparison::Equal => equal,
+        Comparison::GreaterOrEqual => !less,
+        Comparison::Greater => !(less | equal),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day4::rule::Neighborhood;
+    use crate::testing::random_rolls;
+    use rstest::rstest;
+
+    fn grid(rows: &[&str]) -> BitGrid {
+        let mut grid = BitGrid::new(0, rows[0].len());
+        for row in rows {
+            grid.push_row(row.chars().map(|c| c == '@'));
+        }
+        grid
+    }
+
+    fn naive_count(
+        grid: &BitGrid,
+        row: usize,
+        col: usize,
+        offsets: &[(isize, isize)],
+        edges: EdgeMode,
+    ) -> u32 {
+        offsets
+            .iter()
+            .filter(|(dr, dc)| {
+                let neighbor_row = edges.resolve(row as isize + dr, grid.rows());
+                let neighbor_col = edges.resolve(col as isize + dc, grid.cols());
+                match (neighbor_row, neighbor_col) {
+                    (Some(r), Some(c)) => grid.get(r, c),
+                    _ => edges == EdgeMode::Roll,
+                }
+            })
+            .count() as u32
+    }
+
+    #[rstest]
+    fn test_neighbor_count_planes_match_naive_count_across_words(
+        #[values(
+            Neighborhood::Moore,
+            Neighborhood::VonNeumann,
+            Neighborhood::Radius(3),
+            Neighborhood::Custom(vec![(0, -70), (1, 65), (-2, 64), (0, 1), (7, -150)])
+        )]
+        neighborhood: Neighborhood,
+        #[values(EdgeMode::Empty, EdgeMode::Roll, EdgeMode::Wrap, EdgeMode::Mirror)]
+        edges: EdgeMode,
+    ) {
+        let offsets = neighborhood.offsets();
+        // Wide enough that neighbours have to be carried across word boundaries
+        let input = random_rolls(12345, 5, 150, 67);
+        let rows: Vec<&str> = input.lines().collect();
+        let grid = grid(&rows);
+
+        for row in 0..grid.rows() {
+            for col in 0..grid.cols() {
+                let planes = grid.neighbor_count_planes(row, col / WORD_BITS, &offsets, edges);
+                assert_eq!(
+                    count_at(&planes, col % WORD_BITS),
+                    naive_count(&grid, row, col, &offsets, edges),
+                    "row {row}, col {col}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_accessible() {
+        let grid = grid(&["@@@", "@@@", "@.@"]);
+        let result = grid.accessible(&Rule::default());
+        assert_eq!(result.to_string(), "@.@\n...\n@.@\n");
+    }
+
+    #[rstest]
+    #[case(Comparison::Less, 3, "...\n...\n@.@\n")]
+    #[case(Comparison::LessOrEqual, 3, "@.@\n...\n@.@\n")]
+    #[case(Comparison::Equal, 4, "...\n@.@\n...\n")]
+    #[case(Comparison::GreaterOrEqual, 4, ".@.\n@@@\n...\n")]
+    #[case(Comparison::Greater, 6, "...\n.@.\n...\n")]
+    #[case(Comparison::Greater, 300, "...\n...\n...\n")]
+    fn test_accessible_with_comparison(
+        #[case] comparison: Comparison,
+        #[case] threshold: u32,
+        #[case] expected: &str,
+    ) {
+        let grid = grid(&["@@@", "@@@", "@.@"]);
+        let rule = Rule {
+            comparison,
+            threshold,
+            ..Rule::default()
+        };
+        assert_eq!(grid.accessible(&rule).to_string(), expected);
+    }
+
+    #[test]
+    fn test_iter_ones() {
+        let grid = grid(&[".@.", "@.@"]);
+        let ones: Vec<(usize, usize)> = grid.iter_ones().collect();
+        assert_eq!(ones, vec![(0, 1), (1, 0), (1, 2)]);
+    }
+}
@@ -0,0 +1,137 @@
+use std::str::FromStr;
+
+/// How neighbours beyond the edge of the grid are treated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EdgeMode {
+    /// Cells outside the grid are empty.
+    #[default]
+    Empty,
+    /// Cells outside the grid are rolls that can never be removed.
+    Roll,
+    /// The grid wraps around at its edges, like a torus.
+    Wrap,
+    /// The grid is reflected at its outermost cells, so the cell just outside an edge is the
+    /// one just inside it.
+    Mirror,
+}
+
+impl EdgeMode {
+    /// Maps a coordinate along an axis of length `len` to the cell it refers to, or `None` if
+    /// it lies outside the grid.
+    pub fn resolve(&self, coord: isize, len: usize) -> Option<usize> {
+        if coord >= 0 && (coord as usize) < len {
+            return Some(coord as usize);
+        }
+
+        match self {
+            EdgeMode::Empty | EdgeMode::Roll => None,
+            _ if len == 0 => None,
+            EdgeMode::Wrap => Some(coord.rem_euclid(len as isize) as usize),
+            EdgeMode::Mirror if len == 1 => Some(0),
+            EdgeMode::Mirror => {
+                let period = 2 * (len as isize - 1);
+                let folded = coord.rem_euclid(period);
+                Some(if folded < len as isize {
+                    folded
+                } else {
+                    period - folded
+                } as usize)
+            }
+        }
+    }
+
+    /// Returns the coordinates along an axis of length `len` that refer to `coord` when moved
+    /// by `offset`, i.e. every `c` with `resolve(c + offset) == Some(coord)`.
+    pub fn sources(&self, coord: usize, offset: isize, len: usize) -> [Option<usize>; 2] {
+        let inside = |c: isize| (c >= 0 && (c as usize) < len).then_some(c as usize);
+        let coord = coord as isize;
+
+        match self {
+            EdgeMode::Empty | EdgeMode::Roll => [inside(coord - offset), None],
+            EdgeMode::Wrap => [
+                Some((coord - offset).rem_euclid(len as isize) as usize),
+                None,
+            ],
+            EdgeMode::Mirror if len == 1 => [Some(0), None],
+            EdgeMode::Mirror => {
+                let period = 2 * (len as isize - 1);
+                let direct = inside((coord - offset).rem_euclid(period));
+                let reflected = inside((-coord - offset).rem_euclid(period));
+                if direct == reflected {
+                    [direct, None]
+                } else {
+                    [direct, reflected]
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for EdgeMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "empty" => Ok(EdgeMode::Empty),
+            "roll" => Ok(EdgeMode::Roll),
+            "wrap" => Ok(EdgeMode::Wrap),
+            "mirror" => Ok(EdgeMode::Mirror),
+            _ => Err(format!(
+                "unknown edge mode {:?}, expected empty, roll, wrap or mirror",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(EdgeMode::Empty, -1, None)]
+    #[case(EdgeMode::Roll, 4, None)]
+    #[case(EdgeMode::Wrap, -1, Some(3))]
+    #[case(EdgeMode::Wrap, 9, Some(1))]
+    #[case(EdgeMode::Mirror, -1, Some(1))]
+    #[case(EdgeMode::Mirror, -3, Some(3))]
+    #[case(EdgeMode::Mirror, 4, Some(2))]
+    #[case(EdgeMode::Mirror, 7, Some(1))]
+    #[case(EdgeMode::Mirror, 2, Some(2))]
+    fn test_resolve(
+        #[case] edges: EdgeMode,
+        #[case] coord: isize,
+        #[case] expected: Option<usize>,
+    ) {
+        assert_eq!(edges.resolve(coord, 4), expected);
+    }
+
+    #[test]
+    fn test_sources_are_inverse_of_resolve() {
+        for edges in [
+            EdgeMode::Empty,
+            EdgeMode::Roll,
+            EdgeMode::Wrap,
+            EdgeMode::Mirror,
+        ] {
+            for len in 1..6 {
+                for offset in -7..=7 {
+                    for coord in 0..len {
+                        let mut expected: Vec<usize> = (0..len)
+                            .filter(|&c| edges.resolve(c as isize + offset, len) == Some(coord))
+                            .collect();
+                        let mut sources: Vec<usize> = edges
+                            .sources(coord, offset, len)
+                            .into_iter()
+                            .flatten()
+                            .collect();
+                        expected.sort();
+                        sources.sort();
+                        assert_eq!(sources, expected, "{edges:?}, len {len}, offset {offset}");
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use super::bitgrid::BitGrid;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fate {
+    Empty,
+    /// The roll was removed in the given round, counting from 1.
+    Removed(u32),
+    Survives,
+}
+
+/// Records how the rolls of a grid were removed, round by round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovalHistory {
+    cols: usize,
+    // Round in which each cell was removed, 0 if it never was
+    removed_in: Vec<u32>,
+    survivors: BitGrid,
+    rounds: Vec<usize>,
+}
+
+impl RemovalHistory {
+    pub(super) fn new(rows: usize, cols: usize) -> RemovalHistory {
+        RemovalHistory {
+            cols,
+            removed_in: vec![0; rows * cols],
+            survivors: BitGrid::new(rows, cols),
+            rounds: Vec::new(),
+        }
+    }
+
+    pub(super) fn record_round(&mut self, removed: &[(usize, usize)]) {
+        self.rounds.push(removed.len());
+        let round = self.rounds.len() as u32;
+        for &(row, col) in removed {
+            self.removed_in[row * self.cols + col] = round;
+        }
+    }
+
+    pub(super) fn finish(&mut self, survivors: &BitGrid) {
+        self.survivors = survivors.clone();
+    }
+
+    /// Returns how many rolls were removed in each round, excluding the final round in which
+    /// nothing could be removed anymore.
+    pub fn rounds(&self) -> &[usize] {
+        &self.rounds
+    }
+
+    pub fn total_removed(&self) -> usize {
+        self.rounds.iter().sum()
+    }
+
+    pub fn survivors(&self) -> &BitGrid {
+        &self.survivors
+    }
+
+    /// Returns what happened to the cell. Panics if the cell is outside the grid.
+    pub fn fate(&self, row: usize, col: usize) -> Fate {
+        assert!(
+            row < self.survivors.rows() && col < self.cols,
+            "cell out of bounds"
+        );
+        match self.removed_in[row * self.cols + col] {
+            0 if self.survivors.get(row, col) => Fate::Survives,
+            0 => Fate::Empty,
+            round => Fate::Removed(round),
+        }
+    }
+}
+
+/// Prints the grid with the round in which each roll was removed. Empty cells are shown as
+/// `.` and rolls that were never removed as `@`.
+impl fmt::Display for RemovalHistory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.rounds.len().to_string().len();
+        for row in 0..self.survivors.rows() {
+            for col in 0..self.cols {
+                if col > 0 {
+                    write!(f, " ")?;
+                }
+                match self.fate(row, col) {
+                    Fate::Empty => write!(f, "{:>width$}", '.')?,
+                    Fate::Removed(round) => write!(f, "{:>width$}", round)?,
+                    Fate::Survives => write!(f, "{:>width$}", '@')?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
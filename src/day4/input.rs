@@ -0,0 +1,79 @@
+use super::Grid;
+use super::options::USAGE;
+use super::parse::{ParseError, parse_coordinates, parse_grid, parse_space};
+use super::rule::Rule;
+use super::space::Space;
+use super::sparse::SparseGrid;
+use std::error::Error;
+use std::fmt;
+use std::io::BufRead;
+
+/// Why the rolls could not be read: the input is malformed, or the rule does not work for
+/// the grid.
+#[derive(Debug)]
+pub enum InputError {
+    Parse(ParseError),
+    Rule(String),
+}
+
+impl InputError {
+    /// Prints the error and exits, with status 1 for malformed input and with the usage and
+    /// status 2 for an invalid rule.
+    pub fn exit(self) -> ! {
+        match self {
+            InputError::Parse(error) => {
+                eprintln!("Invalid input: {}", error);
+                std::process::exit(1);
+            }
+            InputError::Rule(error) => {
+                eprintln!("{}\n\n{}", error, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::Parse(error) => write!(f, "{}", error),
+            InputError::Rule(error) => write!(f, "invalid rule: {}", error),
+        }
+    }
+}
+
+impl Error for InputError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InputError::Parse(error) => Some(error),
+            InputError::Rule(_) => None,
+        }
+    }
+}
+
+impl From<ParseError> for InputError {
+    fn from(error: ParseError) -> Self {
+        InputError::Parse(error)
+    }
+}
+
+/// Parses a grid with [`parse_grid`] and applies the rule to it.
+pub fn read_grid(reader: &mut dyn BufRead, rule: Rule) -> Result<Grid, InputError> {
+    parse_grid(reader)?
+        .with_rule(rule)
+        .map_err(InputError::Rule)
+}
+
+/// Parses a space with [`parse_space`] and applies the rule to it.
+pub fn read_space(reader: &mut dyn BufRead, rule: Rule) -> Result<Space, InputError> {
+    parse_space(reader)?
+        .with_rule(rule)
+        .map_err(InputError::Rule)
+}
+
+/// Parses a list of roll positions with [`parse_coordinates`] and applies the rule to it.
+pub fn read_coordinates(reader: &mut dyn BufRead, rule: Rule) -> Result<SparseGrid, InputError> {
+    parse_coordinates(reader)?
+        .with_rule(rule)
+        .map_err(InputError::Rule)
+}
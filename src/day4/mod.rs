@@ -0,0 +1,537 @@
+mod backend;
+mod bitgrid;
+mod edge;
+mod history;
+mod input;
+mod options;
+mod parse;
+mod removal;
+mod render;
+mod rule;
+mod space;
+mod sparse;
+mod tui;
+
+pub use backend::Backend;
+pub use bitgrid::BitGrid;
+pub use edge::EdgeMode;
+pub use history::{Fate, RemovalHistory};
+pub use input::{InputError, read_coordinates, read_grid, read_space};
+pub use options::{Options, USAGE};
+pub use parse::{ParseError, parse_coordinates, parse_grid, parse_space};
+pub use render::{ImageFormat, Renderer};
+pub use rule::{Comparison, Neighborhood, Rule};
+pub use space::Space;
+pub use sparse::SparseGrid;
+pub use tui::{Command, Tui, TuiError, spawn_command_reader};
+
+use removal::{Removal, Rows, Topology};
+use std::convert::Infallible;
+use std::ops::Range;
+
+pub struct Grid {
+    plane: Plane,
+    rule: Rule,
+    removal: Removal<(usize, usize)>,
+    threads: usize,
+}
+
+// The rolls of a grid together with how their neighbours are found
+struct Plane {
+    rolls: BitGrid,
+    offsets: Vec<(isize, isize)>,
+    edges: EdgeMode,
+}
+
+impl Topology for Plane {
+    type Cell = (usize, usize);
+
+    fn slots(&self) -> usize {
+        self.rolls.rows() * self.rolls.cols()
+    }
+
+    #[inline]
+    fn index(&self, (row, col): (usize, usize)) -> usize {
+        row * self.rolls.cols() + col
+    }
+
+    #[inline]
+    fn is_roll(&self, (row, col): (usize, usize)) -> bool {
+        self.rolls.get(row, col)
+    }
+
+    fn remove(&mut self, (row, col): (usize, usize)) {
+        self.rolls.set(row, col, false);
+    }
+
+    fn for_each_roll(&self, mut visit: impl FnMut((usize, usize), u32)) {
+        for (cell, count) in self.rolls.neighbor_counts(&self.offsets, self.edges) {
+            visit(cell, count);
+        }
+    }
+
+    #[inline]
+    fn for_each_observer(&self, (row, col): (usize, usize), mut visit: impl FnMut((usize, usize))) {
+        let (rows, cols) = (self.rolls.rows(), self.rolls.cols());
+        for &(dr, dc) in self.offsets.iter() {
+            // Find the rolls that see the removed roll at this offset, taking wrapped and
+            // mirrored edges into account
+            let source_cols = self.edges.sources(col, dc, cols);
+            for source_row in self.edges.sources(row, dr, rows).into_iter().flatten() {
+                for source_col in source_cols.into_iter().flatten() {
+                    visit((source_row, source_col));
+                }
+            }
+        }
+    }
+}
+
+impl Rows for Plane {
+    fn rows(&self) -> usize {
+        self.rolls.rows()
+    }
+
+    fn row_slots(&self) -> usize {
+        self.rolls.cols()
+    }
+
+    fn row_of(&self, (row, _): (usize, usize)) -> usize {
+        row
+    }
+
+    fn halo(&self) -> Option<usize> {
+        // Mirrored edges only reflect rows within reach, but wrapped edges connect the first
+        // and the last rows
+        if self.edges == EdgeMode::Wrap {
+            return None;
+        }
+        Some(
+            self.offsets
+                .iter()
+                .map(|(dr, _)| dr.unsigned_abs())
+                .max()
+                .unwrap_or(0),
+        )
+    }
+
+    fn for_each_roll_in(&self, rows: Range<usize>, mut visit: impl FnMut((usize, usize), u32)) {
+        for (cell, count) in self
+            .rolls
+            .neighbor_counts_in(rows, &self.offsets, self.edges)
+        {
+            visit(cell, count);
+        }
+    }
+}
+
+impl Grid {
+    pub fn from_rolls(rolls: BitGrid) -> Grid {
+        let rule = Rule::default();
+        Grid {
+            plane: Plane {
+                rolls,
+                offsets: rule.neighborhood.offsets(),
+                edges: rule.edges,
+            },
+            rule,
+            removal: Removal::new(),
+            threads: 1,
+        }
+    }
+
+    /// Sets the number of threads used to find accessible rolls and to remove them. Rows are
+    /// split into a band per thread, the results do not depend on the number of threads.
+    pub fn with_threads(mut self, threads: usize) -> Grid {
+        assert!(threads > 0, "at least one thread is needed");
+        self.threads = threads;
+        self
+    }
+
+    /// Replaces the rule that decides which rolls are accessible. Must be set before any rolls
+    /// are removed. Fails if the neighbourhood has too many cells.
+    pub fn with_rule(mut self, rule: Rule) -> Result<Grid, String> {
+        assert!(
+            !self.removal.is_started(),
+            "rolls have already been removed"
+        );
+        self.plane.offsets = rule.neighborhood.checked_offsets()?;
+        self.plane.edges = rule.edges;
+        self.rule = rule;
+        Ok(self)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.plane.rolls.rows()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.plane.rolls.cols()
+    }
+
+    pub fn rolls(&self) -> &BitGrid {
+        &self.plane.rolls
+    }
+
+    pub fn get_neighbors(&self, row: usize, col: usize) -> Option<i32> {
+        let rolls = &self.plane.rolls;
+        if !rolls.get(row, col) {
+            return None;
+        }
+
+        let edges = self.rule.edges;
+        Some(
+            self.plane
+                .offsets
+                .iter()
+                .filter(|(dr, dc)| {
+                    let neighbor_row = edges.resolve(row as isize + dr, self.rows());
+                    let neighbor_col = edges.resolve(col as isize + dc, self.cols());
+                    match (neighbor_row, neighbor_col) {
+                        (Some(row), Some(col)) => rolls.get(row, col),
+                        _ => edges == EdgeMode::Roll,
+                    }
+                })
+                .count() as i32,
+        )
+    }
+
+    /// Returns the rolls that can be reached by a forklift right now, as a grid of their own.
+    pub fn accessible_grid(&self) -> BitGrid {
+        self.plane
+            .rolls
+            .accessible_with_threads(&self.rule, self.threads)
+    }
+
+    /// Keeps removing rolls until none are accessible anymore, recording in which round each
+    /// roll was removed.
+    pub fn remove_all_recorded(&mut self) -> RemovalHistory {
+        let Ok(history) = self.remove_all_with(|_, _| Ok::<(), Infallible>(()));
+        history
+    }
+
+    /// Like [`Grid::remove_all_recorded`], but calls `on_round` with the grid and the removed rolls
+    /// after each round. Stops early if `on_round` returns an error.
+    pub fn remove_all_with<E>(
+        &mut self,
+        mut on_round: impl FnMut(&Grid, &[(usize, usize)]) -> Result<(), E>,
+    ) -> Result<RemovalHistory, E> {
+        let mut history = RemovalHistory::new(self.rows(), self.cols());
+        loop {
+            let removed = self.remove_round();
+            if removed.is_empty() {
+                break;
+            }
+            history.record_round(&removed);
+            on_round(self, &removed)?;
+        }
+        history.finish(&self.plane.rolls);
+        Ok(history)
+    }
+
+    /// Iterates over all rolls together with their number of neighbouring rolls.
+    pub fn neighbor_counts(&self) -> impl Iterator<Item = ((usize, usize), u32)> + '_ {
+        self.plane
+            .rolls
+            .neighbor_counts(&self.plane.offsets, self.rule.edges)
+    }
+
+    // Removes the accessible rolls and returns their positions
+    fn remove_round(&mut self) -> Vec<(usize, usize)> {
+        if self.threads > 1 {
+            self.removal
+                .round_parallel(&mut self.plane, &self.rule, self.threads)
+        } else {
+            self.removal.round(&mut self.plane, &self.rule)
+        }
+    }
+}
+
+impl Backend for Grid {
+    type Cell = (usize, usize);
+
+    fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn count_rolls(&self) -> usize {
+        self.plane.rolls.count_ones()
+    }
+
+    fn accessible(&self) -> Vec<(usize, usize)> {
+        self.accessible_grid().iter_ones().collect()
+    }
+
+    fn count_accessible(&self) -> usize {
+        self.accessible_grid().count_ones()
+    }
+
+    /// Only rolls next to a roll removed in the previous round are re-checked.
+    fn remove(&mut self) -> i32 {
+        self.remove_round().len() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Backend, Comparison, EdgeMode, Fate, Neighborhood, Rule};
+    use crate::testing::random_rolls;
+    use rstest::rstest;
+
+    #[test]
+    fn test_parse_grid() {
+        let input = "..@.\n@@..\n.@.@\n";
+        let mut reader = std::io::Cursor::new(input);
+        let grid = super::parse_grid(&mut reader).unwrap();
+        assert_eq!(grid.rows(), 3);
+        assert_eq!(grid.cols(), 4);
+        assert_eq!(grid.rolls().to_string(), input);
+    }
+
+    #[rstest]
+    #[case(b"..@.\n@@..\n.@.@\n", 2, 0, None)] // cell is '.', has no neighbors
+    #[case(b"..@.\n@@..\n.@.@\n", 2, 1, Some(2))]
+    fn test_get_neighbors(
+        #[case] input: &[u8],
+        #[case] row: usize,
+        #[case] col: usize,
+        #[case] expected: Option<i32>,
+    ) {
+        let mut reader = std::io::Cursor::new(input);
+        let grid = super::parse_grid(&mut reader).unwrap();
+        let neighbors = grid.get_neighbors(row, col);
+        assert_eq!(neighbors, expected);
+    }
+
+    #[test]
+    fn test_remove_rounds() {
+        let input = "@@@@@\n@@@@@\n@@@@@\n";
+        let mut reader = std::io::Cursor::new(input);
+        let mut grid = super::parse_grid(&mut reader).unwrap();
+        // Only the corners are accessible at first, then the erosion walks inwards
+        assert_eq!(grid.remove(), 4);
+        assert_eq!(grid.remove(), 2);
+        assert_eq!(grid.remove(), 4);
+        assert_eq!(grid.remove(), 4);
+        assert_eq!(grid.remove(), 1);
+        assert_eq!(grid.remove(), 0);
+    }
+
+    #[test]
+    fn test_remove_with_von_neumann_neighborhood() {
+        let input = "@@@@@\n@@@@@\n@@@@@\n";
+        let mut reader = std::io::Cursor::new(input);
+        let rule = Rule {
+            neighborhood: Neighborhood::VonNeumann,
+            comparison: Comparison::Less,
+            threshold: 3,
+            ..Rule::default()
+        };
+        let mut grid = super::parse_grid(&mut reader)
+            .unwrap()
+            .with_rule(rule)
+            .unwrap();
+        // Only the corners have fewer than three orthogonal neighbours at first
+        assert_eq!(grid.remove(), 4);
+        assert_eq!(grid.remove(), 6);
+        assert_eq!(grid.remove(), 4);
+        assert_eq!(grid.remove(), 1);
+        assert_eq!(grid.remove(), 0);
+    }
+
+    #[test]
+    fn test_remove_with_asymmetric_neighborhood() {
+        // Only the roll to the east counts, so rolls are removed from the east end of each run
+        let input = "@@@.@@\n";
+        let mut reader = std::io::Cursor::new(input);
+        let rule = Rule {
+            neighborhood: Neighborhood::Custom(vec![(0, 1)]),
+            comparison: Comparison::Less,
+            threshold: 1,
+            ..Rule::default()
+        };
+        let mut grid = super::parse_grid(&mut reader)
+            .unwrap()
+            .with_rule(rule)
+            .unwrap();
+        assert_eq!(grid.remove(), 2);
+        assert_eq!(grid.remove(), 2);
+        assert_eq!(grid.remove(), 1);
+        assert_eq!(grid.remove(), 0);
+    }
+
+    #[test]
+    fn test_with_rule_rejects_large_neighborhoods() {
+        let grid = super::parse_grid(&mut "@@\n@@\n".as_bytes()).unwrap();
+        let rule = Rule {
+            neighborhood: Neighborhood::Radius(8),
+            ..Rule::default()
+        };
+        assert!(grid.with_rule(rule).is_err());
+    }
+
+    #[test]
+    fn test_remove_with_greater_comparison_only_removes_once() {
+        let input = "@@@@@\n@@@@@\n@@@@@\n";
+        let mut reader = std::io::Cursor::new(input);
+        let rule = Rule {
+            neighborhood: Neighborhood::Moore,
+            comparison: Comparison::Greater,
+            threshold: 5,
+            ..Rule::default()
+        };
+        let mut grid = super::parse_grid(&mut reader)
+            .unwrap()
+            .with_rule(rule)
+            .unwrap();
+        assert_eq!(grid.remove(), 3);
+        assert_eq!(grid.remove(), 0);
+    }
+
+    #[rstest]
+    fn test_parallel_removal_matches_single_thread(
+        #[values(EdgeMode::Empty, EdgeMode::Roll, EdgeMode::Wrap, EdgeMode::Mirror)]
+        edges: EdgeMode,
+        #[values(2, 4, 7, 64)] threads: usize,
+    ) {
+        let input = random_rolls(0xbadcafe, 40, 90, 70);
+        let rule = Rule {
+            neighborhood: Neighborhood::Radius(2),
+            edges,
+            threshold: 14,
+            ..Rule::default()
+        };
+        let mut grid = super::parse_grid(&mut input.as_bytes())
+            .unwrap()
+            .with_rule(rule.clone())
+            .unwrap();
+        let mut parallel = super::parse_grid(&mut input.as_bytes())
+            .unwrap()
+            .with_rule(rule)
+            .unwrap()
+            .with_threads(threads);
+
+        assert_eq!(parallel.accessible_grid(), grid.accessible_grid());
+        let history = grid.remove_all_recorded();
+        assert!(history.rounds().len() > 2);
+        assert_eq!(parallel.remove_all_recorded(), history);
+    }
+
+    #[rstest]
+    fn test_remove_matches_full_rescan(
+        #[values(EdgeMode::Empty, EdgeMode::Roll, EdgeMode::Wrap, EdgeMode::Mirror)]
+        edges: EdgeMode,
+        #[values(
+            Neighborhood::Moore,
+            Neighborhood::VonNeumann,
+            Neighborhood::Custom(vec![(0, 1), (1, 1), (-2, 0)])
+        )]
+        neighborhood: Neighborhood,
+        #[values(1, 3)] threads: usize,
+    ) {
+        let input = random_rolls(0x1234567, 12, 70, 70);
+        let rule = Rule {
+            neighborhood,
+            edges,
+            threshold: 3,
+            ..Rule::default()
+        };
+        let mut grid = super::parse_grid(&mut input.as_bytes())
+            .unwrap()
+            .with_rule(rule.clone())
+            .unwrap()
+            .with_threads(threads);
+        let mut rescanned = super::parse_grid(&mut input.as_bytes())
+            .unwrap()
+            .with_rule(rule)
+            .unwrap()
+            .with_threads(threads);
+
+        loop {
+            // Remove everything that is accessible by looking at every roll again
+            let accessible = rescanned.accessible_grid();
+            for (row, col) in accessible.iter_ones() {
+                rescanned.plane.rolls.set(row, col, false);
+            }
+
+            let removed = grid.remove();
+            assert_eq!(removed as usize, accessible.count_ones());
+            assert_eq!(grid.rolls(), rescanned.rolls());
+            if removed == 0 {
+                break;
+            }
+        }
+    }
+
+    #[rstest]
+    #[case(EdgeMode::Empty, 4)]
+    #[case(EdgeMode::Roll, 0)]
+    #[case(EdgeMode::Wrap, 0)]
+    #[case(EdgeMode::Mirror, 0)]
+    fn test_accessible_with_edges(#[case] edges: EdgeMode, #[case] expected: usize) {
+        let input = "@@@@\n@@@@\n@@@@\n";
+        let rule = Rule {
+            edges,
+            ..Rule::default()
+        };
+        let grid = super::parse_grid(&mut input.as_bytes())
+            .unwrap()
+            .with_rule(rule)
+            .unwrap();
+        assert_eq!(grid.count_accessible(), expected);
+    }
+
+    #[test]
+    fn test_get_neighbors_with_wrapped_edges() {
+        let input = "@...\n....\n...@\n";
+        let rule = Rule {
+            edges: EdgeMode::Wrap,
+            ..Rule::default()
+        };
+        let grid = super::parse_grid(&mut input.as_bytes())
+            .unwrap()
+            .with_rule(rule)
+            .unwrap();
+        assert_eq!(grid.get_neighbors(0, 0), Some(1));
+    }
+
+    #[test]
+    fn test_remove_all_records_history() {
+        let input = "@@@@@\n@@@@@\n@@@@@\n...@.\n";
+        let mut grid = super::parse_grid(&mut input.as_bytes()).unwrap();
+        let history = grid.remove_all_recorded();
+        assert_eq!(history.rounds(), &[4, 2, 3, 3, 4]);
+        assert_eq!(history.total_removed(), 16);
+        assert_eq!(history.fate(0, 0), Fate::Removed(1));
+        assert_eq!(history.fate(1, 2), Fate::Removed(5));
+        assert_eq!(history.fate(3, 0), Fate::Empty);
+        assert_eq!(
+            history.to_string(),
+            "1 3 5 4 1\n2 4 5 5 3\n1 3 5 4 2\n. . . 1 .\n"
+        );
+    }
+
+    #[test]
+    fn test_remove_all_keeps_survivors() {
+        let input = "@@@@\n@@@@\n@@@@\n@@@@\n";
+        let rule = Rule {
+            edges: EdgeMode::Roll,
+            ..Rule::default()
+        };
+        let mut grid = super::parse_grid(&mut input.as_bytes())
+            .unwrap()
+            .with_rule(rule)
+            .unwrap();
+        let history = grid.remove_all_recorded();
+        assert!(history.rounds().is_empty());
+        assert_eq!(history.fate(2, 1), Fate::Survives);
+        assert_eq!(history.survivors().count_ones(), 16);
+    }
+
+    #[test]
+    #[should_panic(expected = "cell out of bounds")]
+    fn test_fate_out_of_bounds() {
+        let mut grid = super::parse_grid(&mut "@@@\n@@@\n".as_bytes()).unwrap();
+        // Column 3 would otherwise be read as the first cell of the next row
+        grid.remove_all_recorded().fate(0, 3);
+    }
+}
@@ -0,0 +1,255 @@
+use super::render::ImageFormat;
+use super::rule::Rule;
+use crate::Part;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Options:
+  --neighborhood N   moore (default), von-neumann, radius:R or mask:ROWS,
+                     e.g. mask:.#./#.#/.#.
+  --threshold T      condition on the number of neighbouring rolls that makes a roll
+                     accessible, e.g. <4 (default), <=2, =3, >=5
+  --edges E          treat cells beyond the edge of the grid as empty (default), as rolls
+                     that are never removed (roll), wrap around (wrap) or mirror the grid
+                     (mirror)
+  --history          also print how many rolls were removed in each round and a grid of
+                     the round in which each roll was removed (part 2 only)
+  --render DIR       write an image of the grid after each removal round into DIR
+                     (part 2 only)
+  --format F         image format for --render, png (default) or ppm
+  --scale S          size of a cell in pixels for --render, 4 by default
+  --tui              animate the removal rounds in the terminal, controlled by typing
+                     commands followed by Enter: nothing to step, p to play or pause and
+                     q to quit; cannot be combined with --history or --render (part 2
+                     only)
+  --delay MS         time between rounds while playing the animation, 200 by default
+  --threads N        number of threads evaluating bands of rows in parallel, 1 by default;
+                     has no effect with --layered or --coordinates
+  --layered          read the grid as layers separated by a blank line, with blocks of
+                     layers separated by one more blank line per dimension; cannot be
+                     combined with --history, --render or --tui
+  --coordinates      read the rolls as a list of ROW,COL positions, one per line, and
+                     store only the parts of the grid that contain rolls; cannot be
+                     combined with --layered, --history, --render or --tui";
+
+// Options that only affect the removal rounds of part 2
+const PART_TWO_ONLY: &[&str] = &[
+    "--history",
+    "--render",
+    "--format",
+    "--scale",
+    "--tui",
+    "--delay",
+];
+
+const DEFAULT_SCALE: usize = 4;
+const DEFAULT_DELAY: Duration = Duration::from_millis(200);
+
+/// Command line options shared by both parts of day 4.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub rule: Rule,
+    pub history: bool,
+    pub render: Option<PathBuf>,
+    pub format: ImageFormat,
+    pub scale: usize,
+    pub tui: bool,
+    pub delay: Duration,
+    pub threads: usize,
+    pub layered: bool,
+    pub coordinates: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            rule: Rule::default(),
+            history: false,
+            render: None,
+            format: ImageFormat::default(),
+            scale: DEFAULT_SCALE,
+            tui: false,
+            delay: DEFAULT_DELAY,
+            threads: 1,
+            layered: false,
+            coordinates: false,
+        }
+    }
+}
+
+impl Options {
+    /// Parses the options of the given part, rejecting those that only apply to the other one.
+    pub fn parse(part: Part, args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options::default();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if part == Part::One && PART_TWO_ONLY.contains(&arg.as_str()) {
+                return Err(format!("{} is only supported in part 2", arg));
+            }
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--neighborhood" => options.rule.neighborhood = value()?.parse()?,
+                "--threshold" => options.rule.parse_threshold(&value()?)?,
+                "--edges" => options.rule.edges = value()?.parse()?,
+                "--history" => options.history = true,
+                "--render" => options.render = Some(PathBuf::from(value()?)),
+                "--format" => options.format = value()?.parse()?,
+                "--scale" => {
+                    let scale = value()?;
+                    options.scale = match scale.parse() {
+                        Ok(scale) if scale > 0 => scale,
+                        _ => return Err(format!("invalid scale {:?}", scale)),
+                    }
+                }
+                "--tui" => options.tui = true,
+                "--delay" => {
+                    let delay = value()?;
+                    options.delay = Duration::from_millis(
+                        delay
+                            .parse()
+                            .map_err(|_| format!("invalid delay {:?}", delay))?,
+                    );
+                }
+                "--threads" => {
+                    let threads = value()?;
+                    options.threads = match threads.parse() {
+                        Ok(threads) if threads > 0 => threads,
+                        _ => return Err(format!("invalid number of threads {:?}", threads)),
+                    }
+                }
+                "--layered" => options.layered = true,
+                "--coordinates" => options.coordinates = true,
+                _ => return Err(format!("unknown option {:?}", arg)),
+            }
+        }
+
+        let drawn = options.history || options.render.is_some() || options.tui;
+        if options.tui && (options.history || options.render.is_some()) {
+            return Err("--tui cannot be combined with --history or --render".into());
+        }
+        if options.layered && drawn {
+            return Err("--layered cannot be combined with --history, --render or --tui".into());
+        }
+        if options.coordinates && (options.layered || drawn) {
+            return Err(
+                "--coordinates cannot be combined with --layered, --history, --render or --tui"
+                    .into(),
+            );
+        }
+
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day4::edge::EdgeMode;
+    use crate::day4::rule::{Comparison, Neighborhood};
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        Options::parse(Part::Two, args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_defaults() {
+        assert_eq!(parse(&[]), Ok(Options::default()));
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let options = parse(&["--neighborhood", "von-neumann", "--threshold", "<3"]).unwrap();
+        assert_eq!(
+            options.rule,
+            Rule {
+                neighborhood: Neighborhood::VonNeumann,
+                comparison: Comparison::Less,
+                threshold: 3,
+                ..Rule::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_edges() {
+        let options = parse(&["--edges", "wrap"]).unwrap();
+        assert_eq!(options.rule.edges, EdgeMode::Wrap);
+    }
+
+    #[test]
+    fn test_parse_history() {
+        assert!(parse(&["--history"]).unwrap().history);
+        assert!(!parse(&[]).unwrap().history);
+    }
+
+    #[test]
+    fn test_parse_render() {
+        let options = parse(&["--render", "frames", "--format", "ppm", "--scale", "2"]).unwrap();
+        assert_eq!(options.render, Some(PathBuf::from("frames")));
+        assert_eq!(options.format, ImageFormat::Ppm);
+        assert_eq!(options.scale, 2);
+    }
+
+    #[test]
+    fn test_parse_tui() {
+        let options = parse(&["--tui", "--delay", "50"]).unwrap();
+        assert!(options.tui);
+        assert_eq!(options.delay, Duration::from_millis(50));
+        assert_eq!(
+            parse(&["--tui", "--history"]),
+            Err("--tui cannot be combined with --history or --render".to_string())
+        );
+        assert!(parse(&["--render", "frames", "--tui"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_threads() {
+        assert_eq!(parse(&[]).unwrap().threads, 1);
+        assert_eq!(parse(&["--threads", "8"]).unwrap().threads, 8);
+        assert!(parse(&["--threads", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_layered() {
+        assert!(parse(&["--layered", "--threshold", "<13"]).unwrap().layered);
+        assert!(parse(&["--layered", "--tui"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_coordinates() {
+        assert!(parse(&["--coordinates"]).unwrap().coordinates);
+        assert!(parse(&["--coordinates", "--layered"]).is_err());
+        assert!(parse(&["--coordinates", "--history"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_part_two_options_in_part_one() {
+        let part_one =
+            |args: &[&str]| Options::parse(Part::One, args.iter().map(|arg| arg.to_string()));
+        assert_eq!(
+            part_one(&["--history"]),
+            Err("--history is only supported in part 2".to_string())
+        );
+        assert!(part_one(&["--render", "frames"]).is_err());
+        assert!(part_one(&["--scale", "2"]).is_err());
+        assert!(part_one(&["--tui"]).is_err());
+        assert!(part_one(&["--delay", "50"]).is_err());
+        assert!(part_one(&["--threshold", "<3", "--threads", "2"]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse(&["--neighborhood"]).is_err());
+        assert!(parse(&["--threshold", "four"]).is_err());
+        assert!(parse(&["--edges", "sphere"]).is_err());
+        assert!(parse(&["--scale", "0"]).is_err());
+        assert!(parse(&["--format", "gif"]).is_err());
+        assert!(parse(&["--delay", "-1"]).is_err());
+        assert!(parse(&["--verbose"]).is_err());
+    }
+}
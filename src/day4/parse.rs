@@ -0,0 +1,438 @@
+use super::Grid;
+use super::bitgrid::BitGrid;
+use super::space::Space;
+use super::sparse::SparseGrid;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    Empty,
+    /// A row has a different number of cells than the first one. Rows start at 1.
+    RaggedRow {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A blank line is followed by more rows.
+    BlankRow {
+        row: usize,
+    },
+    UnknownCharacter {
+        row: usize,
+        col: usize,
+        character: char,
+    },
+    /// A line of a coordinate list is not of the form `ROW,COL`.
+    InvalidCoordinates {
+        row: usize,
+        text: String,
+    },
+    /// The coordinates of a roll are too large for the grid to hold it.
+    CoordinatesTooLarge {
+        row: usize,
+        text: String,
+    },
+    /// A layer, or a block of layers, has a different size along `axis` than the first one.
+    RaggedBlock {
+        row: usize,
+        axis: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(error) => write!(f, "could not read the grid: {}", error),
+            ParseError::Empty => write!(f, "the grid is empty"),
+            ParseError::RaggedRow {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "row {} has {} cells, but the first row has {}",
+                row, found, expected
+            ),
+            ParseError::BlankRow { row } => {
+                write!(f, "row {} is blank, but more rows follow", row)
+            }
+            ParseError::UnknownCharacter {
+                row,
+                col,
+                character,
+            } => write!(
+                f,
+                "row {}, column {}: unknown cell {:?}, expected '@' or '.'",
+                row, col, character
+            ),
+            ParseError::InvalidCoordinates { row, text } => write!(
+                f,
+                "row {}: expected the coordinates of a roll as ROW,COL, found {:?}",
+                row, text
+            ),
+            ParseError::CoordinatesTooLarge { row, text } => write!(
+                f,
+                "row {}: the coordinates {:?} are too large for a grid",
+                row, text
+            ),
+            ParseError::RaggedBlock {
+                row,
+                axis: 1,
+                expected,
+                found,
+            } => write!(
+                f,
+                "the layer ending at row {} has {} rows, but the first layer has {}",
+                row, found, expected
+            ),
+            ParseError::RaggedBlock {
+                row,
+                axis,
+                expected,
+                found,
+            } => write!(
+                f,
+                "the block ending at row {} has {} layers along axis {}, but the first block has {}",
+                row, found, axis, expected
+            ),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(error: io::Error) -> Self {
+        ParseError::Io(error)
+    }
+}
+
+/// Parses a grid of `@` (roll) and `.` (empty) cells. All rows must have the same length,
+/// CRLF line endings and blank (or whitespace-only) lines at the end are accepted.
+pub fn parse_grid(reader: &mut dyn BufRead) -> Result<Grid, ParseError> {
+    let mut rolls: Option<BitGrid> = None;
+    let mut first_blank_row = None;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        let row = index + 1;
+
+        if line.trim().is_empty() {
+            first_blank_row.get_or_insert(row);
+            continue;
+        }
+        if let Some(blank_row) = first_blank_row {
+            return Err(ParseError::BlankRow { row: blank_row });
+        }
+
+        let cells = parse_row(line, row)?;
+        let rolls = rolls.get_or_insert_with(|| BitGrid::new(0, cells.len()));
+        if cells.len() != rolls.cols() {
+            return Err(ParseError::RaggedRow {
+                row,
+                expected: rolls.cols(),
+                found: cells.len(),
+            });
+        }
+        rolls.push_row(cells);
+    }
+
+    rolls.map(Grid::from_rolls).ok_or(ParseError::Empty)
+}
+
+/// Parses a grid with any number of dimensions, given as layers of rows separated by a blank
+/// line. Blocks of layers are separated by two blank lines, blocks of those by three and so
+/// on. Every layer and block must have the same size.
+pub fn parse_space(reader: &mut dyn BufRead) -> Result<Space, ParseError> {
+    let mut rolls = Vec::new();
+    // Size along each axis once known, starting with the length of a row
+    let mut dims: Vec<Option<usize>> = vec![None];
+    // Number of completed parts of the block that is being read along each axis
+    let mut parts = vec![0, 0];
+    let mut blank_lines = 0;
+    let mut last_row = 0;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        let row = index + 1;
+
+        if line.trim().is_empty() {
+            blank_lines += 1;
+            continue;
+        }
+        if last_row > 0 && blank_lines > 0 {
+            close_blocks(&mut dims, &mut parts, blank_lines, last_row)?;
+        }
+        blank_lines = 0;
+
+        let cells = parse_row(line, row)?;
+        let cols = *dims[0].get_or_insert(cells.len());
+        if cells.len() != cols {
+            return Err(ParseError::RaggedRow {
+                row,
+                expected: cols,
+                found: cells.len(),
+            });
+        }
+        rolls.extend(cells);
+        parts[1] += 1;
+        last_row = row;
+    }
+
+    if last_row == 0 {
+        return Err(ParseError::Empty);
+    }
+    let top_axis = dims.len();
+    close_blocks(&mut dims, &mut parts, top_axis - 1, last_row)?;
+    dims.push(Some(parts[top_axis]));
+
+    Ok(Space::new(dims.into_iter().flatten().collect(), rolls))
+}
+
+/// Parses a list of roll positions, one `ROW,COL` pair per line. The grid is just large enough
+/// to hold every roll. Blank lines are skipped.
+pub fn parse_coordinates(reader: &mut dyn BufRead) -> Result<SparseGrid, ParseError> {
+    let mut rolls = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let coordinates = line.split_once(',').and_then(|(row, col)| {
+            Some((
+                row.trim().parse::<usize>().ok()?,
+                col.trim().parse::<usize>().ok()?,
+            ))
+        });
+        let Some((row, col)) = coordinates else {
+            return Err(ParseError::InvalidCoordinates {
+                row: index + 1,
+                text: line.to_string(),
+            });
+        };
+        // The grid has to be one larger than the last roll along each axis
+        if row.checked_add(1).is_none() || col.checked_add(1).is_none() {
+            return Err(ParseError::CoordinatesTooLarge {
+                row: index + 1,
+                text: line.to_string(),
+            });
+        }
+        rolls.push((row, col));
+    }
+
+    let rows = rolls.iter().map(|&(row, _)| row + 1).max();
+    let cols = rolls.iter().map(|&(_, col)| col + 1).max();
+    let (Some(rows), Some(cols)) = (rows, cols) else {
+        return Err(ParseError::Empty);
+    };
+    let mut grid = SparseGrid::new(rows, cols);
+    for (row, col) in rolls {
+        grid.insert(row, col);
+    }
+    Ok(grid)
+}
+
+// Ends the blocks along the axes up to and including `last_axis`, checking that each one has
+// the same size as the first block along that axis
+fn close_blocks(
+    dims: &mut Vec<Option<usize>>,
+    parts: &mut Vec<usize>,
+    last_axis: usize,
+    row: usize,
+) -> Result<(), ParseError> {
+    for axis in 1..=last_axis {
+        if dims.len() <= axis {
+            dims.resize(axis + 1, None);
+        }
+        if parts.len() <= axis + 1 {
+            parts.resize(axis + 2, 0);
+        }
+        let found = std::mem::take(&mut parts[axis]);
+        match dims[axis] {
+            Some(expected) if expected != found => {
+                return Err(ParseError::RaggedBlock {
+                    row,
+                    axis,
+                    expected,
+                    found,
+                });
+            }
+            _ => dims[axis] = Some(found),
+        }
+        parts[axis + 1] += 1;
+    }
+    Ok(())
+}
+
+fn parse_row(line: &str, row: usize) -> Result<Vec<bool>, ParseError> {
+    line.chars()
+        .enumerate()
+        .map(|(col, character)| match character {
+            '@' => Ok(true),
+            '.' => Ok(false),
+            _ => Err(ParseError::UnknownCharacter {
+                row,
+                col: col + 1,
+                character,
+            }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<Grid, ParseError> {
+        parse_grid(&mut input.as_bytes())
+    }
+
+    #[test]
+    fn test_parse_grid_with_crlf_and_trailing_blank_lines() {
+        let grid = parse("..@.\r\n@@..\r\n.@.@\r\n\r\n\n").unwrap();
+        assert_eq!(grid.rows(), 3);
+        assert_eq!(grid.cols(), 4);
+        assert_eq!(grid.rolls().to_string(), "..@.\n@@..\n.@.@\n");
+    }
+
+    #[test]
+    fn test_parse_grid_with_trailing_whitespace_line() {
+        let grid = parse("@.\n.@\n  \t\n").unwrap();
+        assert_eq!(grid.rows(), 2);
+        assert!(matches!(
+            parse("@.\n \n.@\n"),
+            Err(ParseError::BlankRow { row: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_parse_grid_without_trailing_newline() {
+        let grid = parse("@.\n.@").unwrap();
+        assert_eq!(grid.rolls().to_string(), "@.\n.@\n");
+    }
+
+    #[test]
+    fn test_parse_grid_empty() {
+        assert!(matches!(parse(""), Err(ParseError::Empty)));
+        assert!(matches!(parse("\n\r\n"), Err(ParseError::Empty)));
+    }
+
+    #[test]
+    fn test_parse_grid_ragged_row() {
+        let result = parse("..@.\n@@.\n.@.@\n");
+        assert!(matches!(
+            result,
+            Err(ParseError::RaggedRow {
+                row: 2,
+                expected: 4,
+                found: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_grid_blank_row_in_between() {
+        let result = parse("..@.\n\n.@.@\n");
+        assert!(matches!(result, Err(ParseError::BlankRow { row: 2 })));
+    }
+
+    #[test]
+    fn test_parse_space_layers() {
+        let space =
+            parse_space(&mut "@.@\n...\n\n.@.\n@@@\r\n\r\n.@.\n...\n\n".as_bytes()).unwrap();
+        assert_eq!(space.dimensions(), &[3, 2, 3]);
+        assert!(space.get(&[0, 0, 0]));
+        assert!(space.get(&[1, 1, 1]));
+        assert!(!space.get(&[0, 1, 2]));
+        assert_eq!(space.to_string(), "@.@\n...\n\n.@.\n@@@\n\n.@.\n...\n");
+    }
+
+    #[test]
+    fn test_parse_space_blocks_of_layers() {
+        let input = "@.\n\n.@\n\n\n@@\n\n..\n";
+        let space = parse_space(&mut input.as_bytes()).unwrap();
+        assert_eq!(space.dimensions(), &[2, 1, 2, 2]);
+        assert_eq!(space.to_string(), input);
+
+        let grid = parse_space(&mut "@.\n.@\n".as_bytes()).unwrap();
+        assert_eq!(grid.dimensions(), &[2, 2]);
+    }
+
+    #[test]
+    fn test_parse_space_ragged_layer() {
+        let result = parse_space(&mut "@.\n.@\n\n@@\n\n..\n@.\n".as_bytes());
+        assert!(matches!(
+            result,
+            Err(ParseError::RaggedBlock {
+                row: 4,
+                axis: 1,
+                expected: 2,
+                found: 1
+            })
+        ));
+        assert!(matches!(
+            parse_space(&mut "\n\n".as_bytes()),
+            Err(ParseError::Empty)
+        ));
+    }
+
+    #[test]
+    fn test_parse_coordinates() {
+        let grid = parse_coordinates(&mut "3,4\r\n\n 0 , 1000000000\n3,4\n".as_bytes()).unwrap();
+        assert_eq!(grid.rows(), 4);
+        assert_eq!(grid.cols(), 1_000_000_001);
+        assert_eq!(grid.positions(), vec![(0, 1_000_000_000), (3, 4)]);
+
+        let result = parse_coordinates(&mut "3,4\n3;5\n".as_bytes());
+        assert!(matches!(
+            result,
+            Err(ParseError::InvalidCoordinates { row: 2, .. })
+        ));
+        assert!(matches!(
+            parse_coordinates(&mut "-1,2\n".as_bytes()),
+            Err(ParseError::InvalidCoordinates { row: 1, .. })
+        ));
+        assert!(matches!(
+            parse_coordinates(&mut "\n".as_bytes()),
+            Err(ParseError::Empty)
+        ));
+        let result = parse_coordinates(&mut format!("1,2\n{},0\n", usize::MAX).as_bytes());
+        assert!(matches!(
+            result,
+            Err(ParseError::CoordinatesTooLarge { row: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_grid_unknown_character() {
+        let result = parse("..@.\n@#..\n");
+        assert!(matches!(
+            result,
+            Err(ParseError::UnknownCharacter {
+                row: 2,
+                col: 2,
+                character: '#'
+            })
+        ));
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "row 2, column 2: unknown cell '#', expected '@' or '.'"
+        );
+    }
+}
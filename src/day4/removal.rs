@@ -0,0 +1,290 @@
+use super::rule::Rule;
+use std::ops::Range;
+use std::thread;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A space of cells holding rolls that can be removed round by round.
+pub(super) trait Topology {
+    type Cell: Copy;
+
+    /// Returns the number of slots needed to index every roll.
+    fn slots(&self) -> usize;
+
+    /// Returns the slot of a roll, below [`Topology::slots`].
+    fn index(&self, cell: Self::Cell) -> usize;
+
+    fn is_roll(&self, cell: Self::Cell) -> bool;
+
+    fn remove(&mut self, cell: Self::Cell);
+
+    /// Calls `visit` with every roll and its number of neighbouring rolls.
+    fn for_each_roll(&self, visit: impl FnMut(Self::Cell, u32));
+
+    /// Calls `visit` with every cell that has `cell` as a neighbour, once for each offset at
+    /// which it sees `cell`.
+    fn for_each_observer(&self, cell: Self::Cell, visit: impl FnMut(Self::Cell));
+}
+
+/// A topology whose cells are laid out in rows of consecutive slots, so it can be split into
+/// bands of rows that are worked on in parallel.
+pub(super) trait Rows: Topology + Sync {
+    fn rows(&self) -> usize;
+
+    /// Returns the number of slots in each row.
+    fn row_slots(&self) -> usize;
+
+    fn row_of(&self, cell: Self::Cell) -> usize;
+
+    /// Returns how many rows away from a roll the rolls observing it can be, or `None` if they
+    /// can be anywhere in the grid.
+    fn halo(&self) -> Option<usize>;
+
+    /// Like [`Topology::for_each_roll`], but only for the rolls in the given rows.
+    fn for_each_roll_in(&self, rows: Range<usize>, visit: impl FnMut(Self::Cell, u32));
+}
+
+/// Splits `len` rows into at most `count` bands of about the same size.
+pub(crate) fn bands(len: usize, count: usize) -> Vec<Range<usize>> {
+    let size = len.div_ceil(count.max(1)).max(1);
+    (0..len)
+        .step_by(size)
+        .map(|start| start..(start + size).min(len))
+        .collect()
+}
+
+/// Removes accessible rolls in rounds. Only rolls next to a roll removed in the previous
+/// round are re-checked.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Removal<C> {
+    // Number of neighbouring rolls per roll, only set up once rolls get removed
+    counts: Vec<u8>,
+    // Rolls that have to be checked in the next removal round
+    candidates: Vec<C>,
+    queued: Vec<u64>,
+}
+
+impl<C: Copy> Removal<C> {
+    pub(super) fn new() -> Removal<C> {
+        Removal {
+            counts: Vec::new(),
+            candidates: Vec::new(),
+            queued: Vec::new(),
+        }
+    }
+
+    pub(super) fn is_started(&self) -> bool {
+        !self.counts.is_empty()
+    }
+
+    // Fills in the neighbour counts of all rolls and queues the accessible ones
+    fn start<T: Topology<Cell = C>>(&mut self, space: &T, rule: &Rule) {
+        let slots = space.slots();
+        self.counts = vec![0; slots];
+        self.queued = vec![0; slots.div_ceil(WORD_BITS)];
+
+        space.for_each_roll(|cell, count| {
+            let index = space.index(cell);
+            self.counts[index] = count as u8;
+            if rule.is_accessible(count) {
+                self.queued[index / WORD_BITS] |= 1 << (index % WORD_BITS);
+                self.candidates.push(cell);
+            }
+        });
+    }
+
+    /// Removes the accessible rolls and returns them.
+    pub(super) fn round<T: Topology<Cell = C>>(&mut self, space: &mut T, rule: &Rule) -> Vec<C> {
+        if !self.is_started() {
+            self.start(space, rule);
+        }
+
+        let candidates = std::mem::take(&mut self.candidates);
+        for &cell in candidates.iter() {
+            let index = space.index(cell);
+            self.queued[index / WORD_BITS] &= !(1 << (index % WORD_BITS));
+        }
+
+        let removed: Vec<C> = candidates
+            .into_iter()
+            .filter(|&cell| {
+                space.is_roll(cell) && rule.is_accessible(self.counts[space.index(cell)] as u32)
+            })
+            .collect();
+
+        for &cell in removed.iter() {
+            space.remove(cell);
+        }
+
+        for &cell in removed.iter() {
+            space.for_each_observer(cell, |observer| {
+                if !space.is_roll(observer) {
+                    return;
+                }
+                let index = space.index(observer);
+                let count = &mut self.counts[index];
+                *count = count.saturating_sub(1);
+                let (word, bit) = (index / WORD_BITS, 1 << (index % WORD_BITS));
+                if rule.is_accessible(*count as u32) && self.queued[word] & bit == 0 {
+                    self.queued[word] |= bit;
+                    self.candidates.push(observer);
+                }
+            });
+        }
+
+        removed
+    }
+}
+
+impl<C: Copy + Send + Sync> Removal<C> {
+    // Like `start`, with a thread per band of rows. The candidates end up sorted by slot.
+    fn start_parallel<T: Rows<Cell = C>>(
+        &mut self,
+        space: &T,
+        rule: &Rule,
+        bands: &[Range<usize>],
+    ) {
+        let slots = space.slots();
+        self.counts = vec![0; slots];
+        self.queued = vec![0; slots.div_ceil(WORD_BITS)];
+
+        let row_slots = space.row_slots();
+        let band_counts = split_bands(&mut self.counts, bands, row_slots);
+        self.candidates = thread::scope(|scope| {
+            let handles: Vec<_> = bands
+                .iter()
+                .zip(band_counts)
+                .map(|(band, counts)| {
+                    scope.spawn(move || {
+                        let first_slot = band.start * row_slots;
+                        let mut candidates = Vec::new();
+                        space.for_each_roll_in(band.clone(), |cell, count| {
+                            counts[space.index(cell) - first_slot] = count as u8;
+                            if rule.is_accessible(count) {
+                                candidates.push(cell);
+                            }
+                        });
+                        candidates
+                    })
+                })
+                .collect();
+            join_all(handles)
+        });
+    }
+
+    /// Like [`Removal::round`], but splits the rolls into bands of rows that are handled on up
+    /// to `threads` threads. Each band only updates the rolls in its own rows, so the result
+    /// does not depend on the number of threads. Falls back to a single thread if rolls can be
+    /// observed from anywhere in the grid.
+    pub(super) fn round_parallel<T: Rows<Cell = C>>(
+        &mut self,
+        space: &mut T,
+        rule: &Rule,
+        threads: usize,
+    ) -> Vec<C> {
+        let bands = bands(space.rows(), threads);
+        let halo = match space.halo() {
+            Some(halo) if bands.len() > 1 => halo,
+            _ => return self.round(space, rule),
+        };
+        if !self.is_started() {
+            self.start_parallel(space, rule, &bands);
+        }
+
+        // The candidates are sorted by slot, and so are the rolls removed from them
+        let candidates = std::mem::take(&mut self.candidates);
+        let removed: Vec<C> = {
+            let (space, counts) = (&*space, &self.counts);
+            let chunk_size = candidates.len().div_ceil(bands.len()).max(1);
+            thread::scope(|scope| {
+                let handles: Vec<_> = candidates
+                    .chunks(chunk_size)
+                    .map(|chunk| {
+                        scope.spawn(move || {
+                            chunk
+                                .iter()
+                                .copied()
+                                .filter(|&cell| {
+                                    space.is_roll(cell)
+                                        && rule.is_accessible(counts[space.index(cell)] as u32)
+                                })
+                                .collect::<Vec<C>>()
+                        })
+                    })
+                    .collect();
+                join_all(handles)
+            })
+        };
+
+        for &cell in removed.iter() {
+            space.remove(cell);
+        }
+
+        let space = &*space;
+        let row_slots = space.row_slots();
+        let band_counts = split_bands(&mut self.counts, &bands, row_slots);
+        self.candidates = thread::scope(|scope| {
+            let handles: Vec<_> = bands
+                .iter()
+                .zip(band_counts)
+                .map(|(band, counts)| {
+                    let removed = &removed;
+                    scope.spawn(move || {
+                        // Only rolls removed within the halo of the band can be observed by it
+                        let first =
+                            removed.partition_point(|&cell| space.row_of(cell) + halo < band.start);
+                        let last =
+                            removed.partition_point(|&cell| space.row_of(cell) < band.end + halo);
+                        let first_slot = band.start * row_slots;
+
+                        let mut candidates = Vec::new();
+                        for &cell in removed[first..last.max(first)].iter() {
+                            space.for_each_observer(cell, |observer| {
+                                if !band.contains(&space.row_of(observer))
+                                    || !space.is_roll(observer)
+                                {
+                                    return;
+                                }
+                                let count = &mut counts[space.index(observer) - first_slot];
+                                *count = count.saturating_sub(1);
+                                if rule.is_accessible(*count as u32) {
+                                    candidates.push(observer);
+                                }
+                            });
+                        }
+                        candidates.sort_unstable_by_key(|&cell| space.index(cell));
+                        candidates.dedup_by_key(|cell| space.index(*cell));
+                        candidates
+                    })
+                })
+                .collect();
+            join_all(handles)
+        });
+
+        removed
+    }
+}
+
+// Splits per slot values into the parts belonging to each band of rows
+fn split_bands<'a, V>(
+    mut values: &'a mut [V],
+    bands: &[Range<usize>],
+    row_slots: usize,
+) -> Vec<&'a mut [V]> {
+    bands
+        .iter()
+        .map(|band| {
+            let (band_values, rest) =
+                std::mem::take(&mut values).split_at_mut(band.len() * row_slots);
+            values = rest;
+            band_values
+        })
+        .collect()
+}
+
+fn join_all<V>(handles: Vec<thread::ScopedJoinHandle<'_, Vec<V>>>) -> Vec<V> {
+    handles
+        .into_iter()
+        .flat_map(|handle| handle.join().expect("a removal thread panicked"))
+        .collect()
+}
@@ -0,0 +1,331 @@
+use super::Grid;
+use super::bitgrid::BitGrid;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+const EMPTY: [u8; 3] = [24, 24, 24];
+const REMOVED_EARLIER: [u8; 3] = [70, 40, 40];
+const REMOVED_NOW: [u8; 3] = [230, 40, 40];
+// Rolls are shaded from the first colour for no neighbours to the second for a full
+// neighbourhood
+const FEW_NEIGHBORS: [u8; 3] = [250, 220, 80];
+const MANY_NEIGHBORS: [u8; 3] = [40, 80, 200];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImageFormat {
+    Ppm,
+    #[default]
+    Png,
+}
+
+impl ImageFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Ppm => "ppm",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ppm" => Ok(ImageFormat::Ppm),
+            "png" => Ok(ImageFormat::Png),
+            _ => Err(format!("unknown image format {:?}, expected ppm or png", s)),
+        }
+    }
+}
+
+/// Writes one image per removal round into a directory, named `frame_0000.png` and so on.
+/// Frame 0 shows the initial grid. The directory is created if it does not exist yet.
+pub struct Renderer {
+    dir: PathBuf,
+    format: ImageFormat,
+    scale: usize,
+    frame: usize,
+    removed_earlier: Option<BitGrid>,
+}
+
+impl Renderer {
+    pub fn new(dir: impl Into<PathBuf>, format: ImageFormat, scale: usize) -> Renderer {
+        Renderer {
+            dir: dir.into(),
+            format,
+            scale: scale.max(1),
+            frame: 0,
+            removed_earlier: None,
+        }
+    }
+
+    /// Writes the next frame, highlighting the rolls that were removed in the last round.
+    pub fn write_frame(&mut self, grid: &Grid, removed: &[(usize, usize)]) -> io::Result<()> {
+        if self.frame == 0 {
+            fs::create_dir_all(&self.dir).map_err(|error| {
+                io::Error::new(
+                    error.kind(),
+                    format!("could not create {}: {}", self.dir.display(), error),
+                )
+            })?;
+        }
+        let removed_earlier = self
+            .removed_earlier
+            .get_or_insert_with(|| BitGrid::new(grid.rows(), grid.cols()));
+
+        let mut pixels = vec![EMPTY; grid.rows() * grid.cols()];
+        for (row, col) in removed_earlier.iter_ones() {
+            pixels[row * grid.cols() + col] = REMOVED_EARLIER;
+        }
+        for &(row, col) in removed {
+            pixels[row * grid.cols() + col] = REMOVED_NOW;
+            removed_earlier.set(row, col, true);
+        }
+
+        let max_neighbors = grid.plane.offsets.len().max(1) as u32;
+        for ((row, col), count) in grid.neighbor_counts() {
+            pixels[row * grid.cols() + col] = blend(
+                FEW_NEIGHBORS,
+                MANY_NEIGHBORS,
+                count.min(max_neighbors),
+                max_neighbors,
+            );
+        }
+
+        let image = Image::scaled(&pixels, grid.cols(), grid.rows(), self.scale);
+        let path = self.dir.join(format!(
+            "frame_{:04}.{}",
+            self.frame,
+            self.format.extension()
+        ));
+        let mut writer = BufWriter::new(File::create(path)?);
+        match self.format {
+            ImageFormat::Ppm => image.write_ppm(&mut writer)?,
+            ImageFormat::Png => image.write_png(&mut writer)?,
+        }
+        writer.flush()?;
+
+        self.frame += 1;
+        Ok(())
+    }
+}
+
+fn blend(from: [u8; 3], to: [u8; 3], step: u32, steps: u32) -> [u8; 3] {
+    let mut color = [0; 3];
+    for channel in 0..3 {
+        let (from, to) = (from[channel] as i32, to[channel] as i32);
+        color[channel] = (from + (to - from) * step as i32 / steps as i32) as u8;
+    }
+    color
+}
+
+struct Image {
+    width: usize,
+    height: usize,
+    // RGB, row by row
+    data: Vec<u8>,
+}
+
+impl Image {
+    fn scaled(pixels: &[[u8; 3]], width: usize, height: usize, scale: usize) -> Image {
+        let mut data = Vec::with_capacity(pixels.len() * scale * scale * 3);
+        for row in pixels.chunks(width.max(1)).take(height) {
+            let line: Vec<u8> = row
+                .iter()
+                .flat_map(|pixel| std::iter::repeat_n(pixel, scale))
+                .flatten()
+                .copied()
+                .collect();
+            for _ in 0..scale {
+                data.extend_from_slice(&line);
+            }
+        }
+
+        Image {
+            width: width * scale,
+            height: height * scale,
+            data,
+        }
+    }
+
+    fn write_ppm(&self, writer: &mut dyn Write) -> io::Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.data)
+    }
+
+    // Uncompressed PNG: the image data is wrapped in stored deflate blocks, so no compression
+    // library is needed
+    fn write_png(&self, writer: &mut dyn Write) -> io::Result<()> {
+        writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // 8 bits per channel, RGB, default compression, filtering and no interlacing
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        write_chunk(writer, b"IHDR", &header)?;
+
+        // Every scanline starts with its filter type, which is always "none"
+        let mut raw = Vec::with_capacity(self.data.len() + self.height);
+        for line in self
+            .data
+            .chunks(self.width * 3)
+            .filter(|line| !line.is_empty())
+        {
+            raw.push(0);
+            raw.extend_from_slice(line);
+        }
+        write_chunk(writer, b"IDAT", &zlib_stored(&raw))?;
+
+        write_chunk(writer, b"IEND", &[])
+    }
+}
+
+fn write_chunk(writer: &mut dyn Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    let crc = crc32(&[kind.as_slice(), data].concat());
+    writer.write_all(&crc.to_be_bytes())
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = u16::MAX as usize;
+
+    let mut out = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK * 5 + 11);
+    // Deflate with a 32K window and no preset dictionary
+    out.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        out.push(last as u8);
+        out.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        out.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                crc >> 1 ^ 0xedb88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MODULUS: u32 = 65521;
+
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MODULUS;
+        b %= MODULUS;
+    }
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day4::parse_grid;
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae426082);
+        assert_eq!(crc32(b"123456789"), 0xcbf43926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e60398);
+    }
+
+    #[test]
+    fn test_zlib_stored_splits_blocks() {
+        let data = vec![7; 70000];
+        let stored = zlib_stored(&data);
+        // Header, two block headers, the data and the checksum
+        assert_eq!(stored.len(), 2 + 5 + 5 + 70000 + 4);
+        assert_eq!(stored[2], 0);
+        assert_eq!(stored[2 + 5 + 65535], 1);
+    }
+
+    #[test]
+    fn test_write_ppm() {
+        let image = Image::scaled(&[[1, 2, 3], [4, 5, 6]], 2, 1, 2);
+        let mut out = Vec::new();
+        image.write_ppm(&mut out).unwrap();
+        assert_eq!(
+            out,
+            b"P6\n4 2\n255\n\x01\x02\x03\x01\x02\x03\x04\x05\x06\x04\x05\x06\
+              \x01\x02\x03\x01\x02\x03\x04\x05\x06\x04\x05\x06"
+        );
+    }
+
+    #[test]
+    fn test_write_png_chunks() {
+        let image = Image::scaled(&[[255, 0, 0]], 1, 1, 1);
+        let mut out = Vec::new();
+        image.write_png(&mut out).unwrap();
+        assert!(out.starts_with(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR"));
+        assert!(out.ends_with(b"\x00\x00\x00\x00IEND\xae\x42\x60\x82"));
+    }
+
+    #[test]
+    fn test_renderer_writes_one_frame_per_round() {
+        let root = std::env::temp_dir().join(format!("aoc2025-render-{}", std::process::id()));
+        let dir = root.join("frames");
+
+        let mut grid = parse_grid(&mut "@@@@@\n@@@@@\n@@@@@\n".as_bytes()).unwrap();
+        let mut renderer = Renderer::new(&dir, ImageFormat::Ppm, 1);
+        renderer.write_frame(&grid, &[]).unwrap();
+        let history = grid
+            .remove_all_with(|grid, removed| renderer.write_frame(grid, removed))
+            .unwrap();
+
+        for frame in 0..=history.rounds().len() {
+            let image = std::fs::read(dir.join(format!("frame_{:04}.ppm", frame))).unwrap();
+            assert!(image.starts_with(b"P6\n5 3\n255\n"));
+            assert_eq!(image.len(), 11 + 5 * 3 * 3);
+        }
+        // The corners are removed in the first round
+        let first = std::fs::read(dir.join("frame_0001.ppm")).unwrap();
+        assert_eq!(&first[11..14], &REMOVED_NOW);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_renderer_reports_unusable_directory() {
+        let file = std::env::temp_dir().join(format!("aoc2025-render-file-{}", std::process::id()));
+        std::fs::write(&file, "").unwrap();
+
+        let grid = parse_grid(&mut "@@\n".as_bytes()).unwrap();
+        let mut renderer = Renderer::new(file.join("frames"), ImageFormat::Ppm, 1);
+        let error = renderer.write_frame(&grid, &[]).unwrap_err();
+        assert!(
+            error.to_string().starts_with("could not create "),
+            "{}",
+            error
+        );
+
+        std::fs::remove_file(&file).unwrap();
+    }
+}
@@ -0,0 +1,268 @@
+use super::edge::EdgeMode;
+use crate::grid;
+use std::str::FromStr;
+
+// Neighbour counts are stored in a byte per cell while removing rolls
+pub const MAX_NEIGHBORHOOD_SIZE: usize = u8::MAX as usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Neighborhood {
+    /// The eight surrounding cells.
+    Moore,
+    /// The four orthogonally adjacent cells.
+    VonNeumann,
+    /// All cells within the given number of steps in any direction, diagonals included.
+    Radius(usize),
+    /// Arbitrary `(row, col)` offsets relative to the cell.
+    Custom(Vec<(isize, isize)>),
+}
+
+impl Neighborhood {
+    /// Returns the `(row, col)` offsets of the neighbouring cells.
+    pub fn offsets(&self) -> Vec<(isize, isize)> {
+        match self {
+            Neighborhood::Moore => grid::MOORE.to_vec(),
+            Neighborhood::VonNeumann => grid::VON_NEUMANN.to_vec(),
+            Neighborhood::Radius(radius) => {
+                let radius = *radius as isize;
+                (-radius..=radius)
+                    .flat_map(|dr| (-radius..=radius).map(move |dc| (dr, dc)))
+                    .filter(|&offset| offset != (0, 0))
+                    .collect()
+            }
+            Neighborhood::Custom(offsets) => offsets.clone(),
+        }
+    }
+
+    /// Returns the offsets of the neighbouring cells, or an error if there are more than
+    /// [`MAX_NEIGHBORHOOD_SIZE`] of them.
+    pub fn checked_offsets(&self) -> Result<Vec<(isize, isize)>, String> {
+        // Large radii are rejected before their offsets are generated
+        let size = match self {
+            Neighborhood::Radius(radius) => radius
+                .checked_mul(2)
+                .and_then(|side| side.checked_add(1))
+                .and_then(|side| side.checked_mul(side))
+                .map(|cells| cells - 1),
+            _ => Some(self.offsets().len()),
+        };
+        match size {
+            Some(size) if size <= MAX_NEIGHBORHOOD_SIZE => Ok(self.offsets()),
+            _ => Err(format!(
+                "the neighbourhood has more than {} cells",
+                MAX_NEIGHBORHOOD_SIZE
+            )),
+        }
+    }
+
+    /// Builds a custom neighbourhood from a mask with an odd number of rows and columns, given
+    /// as rows separated by `/`. Cells marked with `#` are neighbours of the centre cell.
+    pub fn from_mask(mask: &str) -> Result<Neighborhood, String> {
+        let rows: Vec<&str> = mask.split('/').collect();
+        let cols = rows[0].chars().count();
+        if rows.len().is_multiple_of(2) || cols.is_multiple_of(2) {
+            return Err(format!(
+                "mask {:?} must have an odd number of rows and columns",
+                mask
+            ));
+        }
+        if rows.iter().any(|row| row.chars().count() != cols) {
+            return Err(format!("mask {:?} must be rectangular", mask));
+        }
+
+        let (center_row, center_col) = ((rows.len() / 2) as isize, (cols / 2) as isize);
+        let mut offsets = Vec::new();
+        for (row, line) in rows.iter().enumerate() {
+            for (col, cell) in line.chars().enumerate() {
+                let offset = (row as isize - center_row, col as isize - center_col);
+                match cell {
+                    '#' if offset == (0, 0) => {
+                        return Err(format!("mask {:?} must not mark its centre", mask));
+                    }
+                    '#' => offsets.push(offset),
+                    '.' => {}
+                    _ => return Err(format!("mask {:?} may only contain '#' and '.'", mask)),
+                }
+            }
+        }
+
+        Ok(Neighborhood::Custom(offsets))
+    }
+}
+
+impl FromStr for Neighborhood {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let neighborhood = match s.split_once(':') {
+            None if s == "moore" => Neighborhood::Moore,
+            None if s == "von-neumann" => Neighborhood::VonNeumann,
+            Some(("radius", radius)) => Neighborhood::Radius(
+                radius
+                    .parse()
+                    .map_err(|_| format!("invalid radius {:?}", radius))?,
+            ),
+            Some(("mask", mask)) => Neighborhood::from_mask(mask)?,
+            _ => {
+                return Err(format!(
+                    "unknown neighbourhood {:?}, expected moore, von-neumann, radius:R or mask:ROWS",
+                    s
+                ));
+            }
+        };
+
+        neighborhood.checked_offsets().map_err(|_| {
+            format!(
+                "neighbourhood {:?} has more than {} cells",
+                s, MAX_NEIGHBORHOOD_SIZE
+            )
+        })?;
+        Ok(neighborhood)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    pub fn holds(&self, value: u32, threshold: u32) -> bool {
+        match self {
+            Comparison::Less => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+            Comparison::Equal => value == threshold,
+            Comparison::GreaterOrEqual => value >= threshold,
+            Comparison::Greater => value > threshold,
+        }
+    }
+}
+
+/// Decides which rolls a forklift can access, based on how many of their neighbours are rolls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub neighborhood: Neighborhood,
+    pub comparison: Comparison,
+    pub threshold: u32,
+    pub edges: EdgeMode,
+}
+
+impl Rule {
+    pub fn is_accessible(&self, neighbors: u32) -> bool {
+        self.comparison.holds(neighbors, self.threshold)
+    }
+
+    /// Parses a condition such as `<4` or `>=2` into the comparison and threshold of the rule.
+    /// Without an operator, `<` is assumed.
+    pub fn parse_threshold(&mut self, condition: &str) -> Result<(), String> {
+        let (comparison, threshold) = [
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+            ("=", Comparison::Equal),
+        ]
+        .into_iter()
+        .find_map(|(operator, comparison)| {
+            condition
+                .strip_prefix(operator)
+                .map(|threshold| (comparison, threshold))
+        })
+        .unwrap_or((Comparison::Less, condition));
+
+        self.threshold = threshold
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid threshold {:?}", condition))?;
+        self.comparison = comparison;
+        Ok(())
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule {
+            neighborhood: Neighborhood::Moore,
+            comparison: Comparison::Less,
+            threshold: 4,
+            edges: EdgeMode::Empty,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("moore", 8)]
+    #[case("von-neumann", 4)]
+    #[case("radius:1", 8)]
+    #[case("radius:2", 24)]
+    #[case("mask:.#./#.#/.#.", 4)]
+    #[case("mask:#..../...../...../...../....#", 2)]
+    fn test_parse_neighborhood(#[case] input: &str, #[case] size: usize) {
+        let neighborhood: Neighborhood = input.parse().unwrap();
+        assert_eq!(neighborhood.offsets().len(), size);
+    }
+
+    #[test]
+    fn test_mask_offsets() {
+        let neighborhood = Neighborhood::from_mask("#../..#/...").unwrap();
+        assert_eq!(neighborhood.offsets(), vec![(-1, -1), (0, 1)]);
+    }
+
+    #[rstest]
+    #[case("hexagonal")]
+    #[case("radius:x")]
+    #[case("radius:8")]
+    #[case("mask:.#/#.")]
+    #[case("mask:.../.#./...")]
+    #[case("mask:.../.x./...")]
+    #[case("mask:.../../...")]
+    fn test_parse_neighborhood_invalid(#[case] input: &str) {
+        assert!(input.parse::<Neighborhood>().is_err());
+    }
+
+    #[test]
+    fn test_checked_offsets() {
+        assert_eq!(
+            Neighborhood::Radius(7).checked_offsets().unwrap().len(),
+            224
+        );
+        assert!(Neighborhood::Radius(8).checked_offsets().is_err());
+        assert!(Neighborhood::Radius(usize::MAX).checked_offsets().is_err());
+        let wide = Neighborhood::Custom((1..=256).map(|col| (0, col)).collect());
+        assert!(wide.checked_offsets().is_err());
+    }
+
+    #[rstest]
+    #[case("<4", Comparison::Less, 4)]
+    #[case("4", Comparison::Less, 4)]
+    #[case("<=3", Comparison::LessOrEqual, 3)]
+    #[case("=2", Comparison::Equal, 2)]
+    #[case(">= 5", Comparison::GreaterOrEqual, 5)]
+    #[case(">0", Comparison::Greater, 0)]
+    fn test_parse_threshold(
+        #[case] input: &str,
+        #[case] comparison: Comparison,
+        #[case] threshold: u32,
+    ) {
+        let mut rule = Rule::default();
+        rule.parse_threshold(input).unwrap();
+        assert_eq!(rule.comparison, comparison);
+        assert_eq!(rule.threshold, threshold);
+    }
+
+    #[test]
+    fn test_parse_threshold_invalid() {
+        let mut rule = Rule::default();
+        assert!(rule.parse_threshold("<<4").is_err());
+        assert_eq!(rule, Rule::default());
+    }
+}
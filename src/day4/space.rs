@@ -0,0 +1,387 @@
+use super::backend::Backend;
+use super::edge::EdgeMode;
+use super::removal::{Removal, Topology};
+use super::rule::{MAX_NEIGHBORHOOD_SIZE, Neighborhood, Rule};
+use std::fmt;
+
+/// A grid of rolls with any number of dimensions. Axis 0 runs along a row, axis 1 across the
+/// rows of a layer, axis 2 across the layers and so on.
+pub struct Space {
+    lattice: Lattice,
+    rule: Rule,
+    removal: Removal<usize>,
+}
+
+// The rolls of a space together with how their neighbours are found. Cells are identified by
+// their index in reading order.
+struct Lattice {
+    dims: Vec<usize>,
+    rolls: Vec<bool>,
+    // One coordinate per axis for each offset, one offset after another
+    offsets: Vec<isize>,
+    edges: EdgeMode,
+}
+
+impl Lattice {
+    fn coords(&self, mut index: usize) -> Vec<usize> {
+        self.dims
+            .iter()
+            .map(|&len| {
+                let coord = index % len;
+                index /= len;
+                coord
+            })
+            .collect()
+    }
+
+    fn index(&self, coords: impl DoubleEndedIterator<Item = usize>) -> usize {
+        coords
+            .rev()
+            .zip(self.dims.iter().rev())
+            .fold(0, |index, (coord, &len)| index * len + coord)
+    }
+
+    fn offsets(&self) -> impl Iterator<Item = &[isize]> {
+        self.offsets.chunks(self.dims.len())
+    }
+
+    fn neighbor_count(&self, coords: &[usize]) -> u32 {
+        self.offsets()
+            .filter(|offset| {
+                let neighbor: Option<Vec<usize>> = coords
+                    .iter()
+                    .zip(offset.iter())
+                    .zip(self.dims.iter())
+                    .map(|((&coord, &delta), &len)| self.edges.resolve(coord as isize + delta, len))
+                    .collect();
+                match neighbor {
+                    Some(neighbor) => self.rolls[self.index(neighbor.into_iter())],
+                    None => self.edges == EdgeMode::Roll,
+                }
+            })
+            .count() as u32
+    }
+}
+
+impl Topology for Lattice {
+    type Cell = usize;
+
+    fn slots(&self) -> usize {
+        self.rolls.len()
+    }
+
+    fn index(&self, cell: usize) -> usize {
+        cell
+    }
+
+    fn is_roll(&self, cell: usize) -> bool {
+        self.rolls[cell]
+    }
+
+    fn remove(&mut self, cell: usize) {
+        self.rolls[cell] = false;
+    }
+
+    fn for_each_roll(&self, mut visit: impl FnMut(usize, u32)) {
+        for cell in (0..self.rolls.len()).filter(|&cell| self.rolls[cell]) {
+            visit(cell, self.neighbor_count(&self.coords(cell)));
+        }
+    }
+
+    fn for_each_observer(&self, cell: usize, mut visit: impl FnMut(usize)) {
+        let coords = self.coords(cell);
+        for offset in self.offsets() {
+            let sources: Vec<[Option<usize>; 2]> = coords
+                .iter()
+                .zip(offset.iter())
+                .zip(self.dims.iter())
+                .map(|((&coord, &delta), &len)| self.edges.sources(coord, delta, len))
+                .collect();
+
+            // Mirrored edges can give two sources along an axis, so every combination of them
+            // sees the cell
+            for choice in 0..1usize << sources.len() {
+                let source: Option<Vec<usize>> = sources
+                    .iter()
+                    .enumerate()
+                    .map(|(axis, options)| options[(choice >> axis) & 1])
+                    .collect();
+                if let Some(source) = source {
+                    visit(self.index(source.into_iter()));
+                }
+            }
+        }
+    }
+}
+
+impl Space {
+    /// Creates a space with the given length along each axis, from its cells in reading order.
+    pub fn new(dims: Vec<usize>, rolls: Vec<bool>) -> Space {
+        assert_eq!(
+            dims.iter().product::<usize>(),
+            rolls.len(),
+            "the number of cells must match the dimensions"
+        );
+        let rule = Rule::default();
+        let offsets = offsets(&rule.neighborhood, dims.len()).expect("the default rule fits");
+        Space {
+            lattice: Lattice {
+                dims,
+                rolls,
+                offsets,
+                edges: rule.edges,
+            },
+            rule,
+            removal: Removal::new(),
+        }
+    }
+
+    /// Replaces the rule that decides which rolls are accessible. Must be set before any rolls
+    /// are removed. Fails if the neighbourhood does not fit the number of dimensions.
+    pub fn with_rule(mut self, rule: Rule) -> Result<Space, String> {
+        assert!(
+            !self.removal.is_started(),
+            "rolls have already been removed"
+        );
+        self.lattice.offsets = offsets(&rule.neighborhood, self.lattice.dims.len())?;
+        self.lattice.edges = rule.edges;
+        self.rule = rule;
+        Ok(self)
+    }
+
+    /// Returns the length of the space along each axis.
+    pub fn dimensions(&self) -> &[usize] {
+        &self.lattice.dims
+    }
+
+    pub fn get(&self, coords: &[usize]) -> bool {
+        self.lattice.rolls[self.lattice.index(coords.iter().copied())]
+    }
+
+    pub fn get_neighbors(&self, coords: &[usize]) -> Option<u32> {
+        self.get(coords)
+            .then(|| self.lattice.neighbor_count(coords))
+    }
+}
+
+impl Backend for Space {
+    type Cell = Vec<usize>;
+
+    fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn count_rolls(&self) -> usize {
+        self.lattice.rolls.iter().filter(|&&roll| roll).count()
+    }
+
+    fn accessible(&self) -> Vec<Vec<usize>> {
+        let mut accessible = Vec::new();
+        self.lattice.for_each_roll(|cell, count| {
+            if self.rule.is_accessible(count) {
+                accessible.push(self.lattice.coords(cell));
+            }
+        });
+        accessible
+    }
+
+    fn count_accessible(&self) -> usize {
+        let mut accessible = 0;
+        self.lattice.for_each_roll(|_, count| {
+            if self.rule.is_accessible(count) {
+                accessible += 1;
+            }
+        });
+        accessible
+    }
+
+    fn remove(&mut self) -> i32 {
+        self.removal.round(&mut self.lattice, &self.rule).len() as i32
+    }
+}
+
+// Lists the offsets of a neighbourhood in the given number of dimensions
+fn offsets(neighborhood: &Neighborhood, dims: usize) -> Result<Vec<isize>, String> {
+    let offsets: Vec<Vec<isize>> = match neighborhood {
+        Neighborhood::Moore => cube(1, dims),
+        Neighborhood::VonNeumann => (0..dims)
+            .flat_map(|axis| {
+                [-1, 1].map(|delta| {
+                    let mut offset = vec![0; dims];
+                    offset[axis] = delta;
+                    offset
+                })
+            })
+            .collect(),
+        Neighborhood::Radius(radius) => cube(*radius, dims),
+        Neighborhood::Custom(offsets) if dims == 2 => {
+            offsets.iter().map(|&(row, col)| vec![col, row]).collect()
+        }
+        Neighborhood::Custom(_) => {
+            return Err(format!(
+                "custom neighbourhoods only work in 2 dimensions, not {}",
+                dims
+            ));
+        }
+    };
+
+    if offsets.len() > MAX_NEIGHBORHOOD_SIZE {
+        return Err(format!(
+            "the neighbourhood has {} cells in {} dimensions, at most {} are supported",
+            offsets.len(),
+            dims,
+            MAX_NEIGHBORHOOD_SIZE
+        ));
+    }
+    Ok(offsets.concat())
+}
+
+// All offsets within the given distance along every axis, except the cell itself
+fn cube(radius: usize, dims: usize) -> Vec<Vec<isize>> {
+    let radius = radius as isize;
+    let mut offsets = vec![vec![]];
+    for _ in 0..dims {
+        offsets = offsets
+            .into_iter()
+            .flat_map(|offset: Vec<isize>| {
+                (-radius..=radius).map(move |delta| [offset.as_slice(), &[delta]].concat())
+            })
+            .collect();
+    }
+    offsets.retain(|offset| offset.iter().any(|&delta| delta != 0));
+    offsets
+}
+
+/// Prints the layers of the space as grids separated by a blank line, and the blocks of
+/// layers of higher dimensions by one more blank line per dimension.
+impl fmt::Display for Space {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dims = &self.lattice.dims;
+        let cols = dims.first().copied().unwrap_or(0);
+        if cols == 0 {
+            return Ok(());
+        }
+
+        let rows = self.lattice.rolls.chunks(cols);
+        let row_count = rows.len();
+        for (row, cells) in rows.enumerate() {
+            for &roll in cells {
+                write!(f, "{}", if roll { '@' } else { '.' })?;
+            }
+            writeln!(f)?;
+
+            if row + 1 < row_count {
+                // A blank line for each block of layers that ends here
+                let mut size = 1;
+                for &len in dims[1..dims.len() - 1].iter() {
+                    size *= len;
+                    if (row + 1) % size == 0 {
+                        writeln!(f)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day4::rule::Comparison;
+    use crate::testing::XorShift;
+    use rstest::rstest;
+
+    // A 3x3x3 cube full of rolls with an empty centre
+    fn hollow_cube() -> Space {
+        let rolls = (0..27).map(|cell| cell != 13).collect();
+        Space::new(vec![3, 3, 3], rolls)
+    }
+
+    #[test]
+    fn test_offsets() {
+        assert_eq!(offsets(&Neighborhood::Moore, 3).unwrap().len(), 26 * 3);
+        assert_eq!(offsets(&Neighborhood::VonNeumann, 3).unwrap().len(), 6 * 3);
+        assert_eq!(offsets(&Neighborhood::Moore, 4).unwrap().len(), 80 * 4);
+        assert!(offsets(&Neighborhood::Moore, 6).is_err());
+        assert!(offsets(&Neighborhood::Custom(vec![(0, 1)]), 3).is_err());
+    }
+
+    #[test]
+    fn test_get_neighbors() {
+        let space = hollow_cube();
+        assert_eq!(space.get_neighbors(&[0, 0, 0]), Some(6));
+        assert_eq!(space.get_neighbors(&[1, 0, 0]), Some(10));
+        assert_eq!(space.get_neighbors(&[1, 1, 0]), Some(16));
+        assert_eq!(space.get_neighbors(&[1, 1, 1]), None);
+    }
+
+    #[test]
+    fn test_remove_all() {
+        let rule = Rule {
+            comparison: Comparison::Less,
+            threshold: 13,
+            ..Rule::default()
+        };
+        let mut space = hollow_cube().with_rule(rule).unwrap();
+        assert_eq!(space.count_accessible(), 20);
+        assert_eq!(space.accessible()[0], vec![0, 0, 0]);
+        assert_eq!(space.remove_all(), vec![20, 6]);
+        assert_eq!(space.count_rolls(), 0);
+    }
+
+    // Removing rolls one round at a time must give the same rounds as recounting the
+    // neighbours of every roll after each round
+    #[rstest]
+    fn test_remove_matches_full_rescan(
+        #[values(EdgeMode::Empty, EdgeMode::Roll, EdgeMode::Wrap, EdgeMode::Mirror)]
+        edges: EdgeMode,
+        #[values(Neighborhood::Moore, Neighborhood::VonNeumann)] neighborhood: Neighborhood,
+    ) {
+        let dims = vec![5, 4, 3];
+        let mut random = XorShift::new(7);
+        let rolls: Vec<bool> = (0..60).map(|_| random.chance(80)).collect();
+        let threshold = if neighborhood == Neighborhood::Moore {
+            14
+        } else {
+            4
+        };
+        let rule = Rule {
+            neighborhood,
+            threshold,
+            edges,
+            ..Rule::default()
+        };
+
+        let mut space = Space::new(dims.clone(), rolls.clone())
+            .with_rule(rule.clone())
+            .unwrap();
+        let mut rescanned = Space::new(dims, rolls).with_rule(rule).unwrap();
+        loop {
+            let mut accessible = Vec::new();
+            rescanned.lattice.for_each_roll(|cell, count| {
+                if rescanned.rule.is_accessible(count) {
+                    accessible.push(cell);
+                }
+            });
+            for &cell in accessible.iter() {
+                rescanned.lattice.remove(cell);
+            }
+
+            assert_eq!(space.remove() as usize, accessible.len());
+            assert_eq!(space.lattice.rolls, rescanned.lattice.rolls);
+            if accessible.is_empty() {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        let space = Space::new(
+            vec![2, 1, 2, 2],
+            vec![true, false, false, true, true, true, false, false],
+        );
+        assert_eq!(space.to_string(), "@.\n\n.@\n\n\n@@\n\n..\n");
+    }
+}
@@ -0,0 +1,345 @@
+use super::backend::Backend;
+use super::edge::EdgeMode;
+use super::removal::{Removal, Topology};
+use super::rule::Rule;
+use std::collections::HashMap;
+use std::hash::{BuildHasherDefault, Hasher};
+
+// Rolls are stored in square chunks of 8 by 8 cells, one bit per cell
+const CHUNK_SIZE: usize = 8;
+const CHUNK_CELLS: usize = CHUNK_SIZE * CHUNK_SIZE;
+
+/// A grid of rolls that only stores the chunks of the grid containing rolls, for large grids
+/// that are mostly empty.
+pub struct SparseGrid {
+    field: Field,
+    rule: Rule,
+    removal: Removal<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Chunk {
+    rolls: u64,
+    // The first slot of the chunk, the cells of a chunk use consecutive slots
+    first_slot: usize,
+}
+
+// The rolls of a sparse grid together with how their neighbours are found
+struct Field {
+    rows: usize,
+    cols: usize,
+    chunks: HashMap<(usize, usize), Chunk, BuildHasherDefault<ChunkHasher>>,
+    offsets: Vec<(isize, isize)>,
+    edges: EdgeMode,
+}
+
+// Chunk coordinates are small integers, which do not need the DoS resistant default hasher
+#[derive(Default)]
+struct ChunkHasher(u64);
+
+impl Hasher for ChunkHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u64(byte as u64);
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = (self.0.rotate_left(5) ^ n).wrapping_mul(0x517c_c1b7_2722_0a95);
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+fn chunk_of(row: usize, col: usize) -> ((usize, usize), u64) {
+    let key = (row / CHUNK_SIZE, col / CHUNK_SIZE);
+    let bit = 1 << ((row % CHUNK_SIZE) * CHUNK_SIZE + col % CHUNK_SIZE);
+    (key, bit)
+}
+
+impl Field {
+    fn get(&self, row: usize, col: usize) -> bool {
+        let (key, bit) = chunk_of(row, col);
+        self.chunks
+            .get(&key)
+            .is_some_and(|chunk| chunk.rolls & bit != 0)
+    }
+
+    fn neighbor_count(&self, row: usize, col: usize) -> u32 {
+        self.offsets
+            .iter()
+            .filter(|(dr, dc)| {
+                let neighbor_row = self.edges.resolve(row as isize + dr, self.rows);
+                let neighbor_col = self.edges.resolve(col as isize + dc, self.cols);
+                match (neighbor_row, neighbor_col) {
+                    (Some(row), Some(col)) => self.get(row, col),
+                    _ => self.edges == EdgeMode::Roll,
+                }
+            })
+            .count() as u32
+    }
+
+    fn rolls(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.chunks
+            .iter()
+            .flat_map(|(&(chunk_row, chunk_col), chunk)| {
+                (0..CHUNK_CELLS)
+                    .filter(|&cell| chunk.rolls & (1 << cell) != 0)
+                    .map(move |cell| {
+                        (
+                            chunk_row * CHUNK_SIZE + cell / CHUNK_SIZE,
+                            chunk_col * CHUNK_SIZE + cell % CHUNK_SIZE,
+                        )
+                    })
+            })
+    }
+}
+
+impl Topology for Field {
+    type Cell = (usize, usize);
+
+    fn slots(&self) -> usize {
+        self.chunks.len() * CHUNK_CELLS
+    }
+
+    #[inline]
+    fn index(&self, (row, col): (usize, usize)) -> usize {
+        let (key, bit) = chunk_of(row, col);
+        self.chunks[&key].first_slot + bit.trailing_zeros() as usize
+    }
+
+    #[inline]
+    fn is_roll(&self, (row, col): (usize, usize)) -> bool {
+        self.get(row, col)
+    }
+
+    fn remove(&mut self, (row, col): (usize, usize)) {
+        let (key, bit) = chunk_of(row, col);
+        if let Some(chunk) = self.chunks.get_mut(&key) {
+            chunk.rolls &= !bit;
+        }
+    }
+
+    fn for_each_roll(&self, mut visit: impl FnMut((usize, usize), u32)) {
+        for (row, col) in self.rolls() {
+            visit((row, col), self.neighbor_count(row, col));
+        }
+    }
+
+    #[inline]
+    fn for_each_observer(&self, (row, col): (usize, usize), mut visit: impl FnMut((usize, usize))) {
+        for &(dr, dc) in self.offsets.iter() {
+            let source_cols = self.edges.sources(col, dc, self.cols);
+            for source_row in self.edges.sources(row, dr, self.rows).into_iter().flatten() {
+                for source_col in source_cols.into_iter().flatten() {
+                    visit((source_row, source_col));
+                }
+            }
+        }
+    }
+}
+
+impl SparseGrid {
+    pub fn new(rows: usize, cols: usize) -> SparseGrid {
+        let rule = Rule::default();
+        SparseGrid {
+            field: Field {
+                rows,
+                cols,
+                chunks: HashMap::default(),
+                offsets: rule.neighborhood.offsets(),
+                edges: rule.edges,
+            },
+            rule,
+            removal: Removal::new(),
+        }
+    }
+
+    /// Places a roll. Must be called before any rolls are removed.
+    pub fn insert(&mut self, row: usize, col: usize) {
+        assert!(
+            !self.removal.is_started(),
+            "rolls have already been removed"
+        );
+        assert!(
+            row < self.rows() && col < self.cols(),
+            "({}, {}) is outside the grid",
+            row,
+            col
+        );
+
+        let (key, bit) = chunk_of(row, col);
+        let first_slot = self.field.chunks.len() * CHUNK_CELLS;
+        self.field
+            .chunks
+            .entry(key)
+            .or_insert(Chunk {
+                rolls: 0,
+                first_slot,
+            })
+            .rolls |= bit;
+    }
+
+    /// Replaces the rule that decides which rolls are accessible. Must be set before any rolls
+    /// are removed. Fails if the neighbourhood has too many cells.
+    pub fn with_rule(mut self, rule: Rule) -> Result<SparseGrid, String> {
+        assert!(
+            !self.removal.is_started(),
+            "rolls have already been removed"
+        );
+        self.field.offsets = rule.neighborhood.checked_offsets()?;
+        self.field.edges = rule.edges;
+        self.rule = rule;
+        Ok(self)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.field.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.field.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> bool {
+        self.field.get(row, col)
+    }
+
+    /// Returns the positions of all rolls, sorted by row and column.
+    pub fn positions(&self) -> Vec<(usize, usize)> {
+        let mut rolls: Vec<(usize, usize)> = self.field.rolls().collect();
+        rolls.sort_unstable();
+        rolls
+    }
+
+    pub fn get_neighbors(&self, row: usize, col: usize) -> Option<i32> {
+        self.get(row, col)
+            .then(|| self.field.neighbor_count(row, col) as i32)
+    }
+}
+
+impl Backend for SparseGrid {
+    type Cell = (usize, usize);
+
+    fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    fn count_rolls(&self) -> usize {
+        self.field
+            .chunks
+            .values()
+            .map(|chunk| chunk.rolls.count_ones() as usize)
+            .sum()
+    }
+
+    fn accessible(&self) -> Vec<(usize, usize)> {
+        let mut accessible = Vec::new();
+        self.field.for_each_roll(|cell, count| {
+            if self.rule.is_accessible(count) {
+                accessible.push(cell);
+            }
+        });
+        accessible.sort_unstable();
+        accessible
+    }
+
+    fn count_accessible(&self) -> usize {
+        let mut accessible = 0;
+        self.field.for_each_roll(|_, count| {
+            if self.rule.is_accessible(count) {
+                accessible += 1;
+            }
+        });
+        accessible
+    }
+
+    fn remove(&mut self) -> i32 {
+        self.removal.round(&mut self.field, &self.rule).len() as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day4::{EdgeMode, Neighborhood, parse_grid};
+    use crate::testing::random_rolls;
+    use rstest::rstest;
+
+    fn sparse_from(input: &str) -> SparseGrid {
+        let grid = parse_grid(&mut input.as_bytes()).unwrap();
+        let mut sparse = SparseGrid::new(grid.rows(), grid.cols());
+        for (row, col) in grid.rolls().iter_ones() {
+            sparse.insert(row, col);
+        }
+        sparse
+    }
+
+    #[test]
+    fn test_insert_and_get() {
+        let mut grid = SparseGrid::new(1_000_000_000, 1_000_000_000);
+        grid.insert(999_999_999, 3);
+        grid.insert(999_999_999, 3);
+        grid.insert(5, 123_456_789);
+        assert_eq!(grid.count_rolls(), 2);
+        assert!(grid.get(999_999_999, 3));
+        assert!(!grid.get(999_999_998, 3));
+        assert_eq!(grid.positions(), vec![(5, 123_456_789), (999_999_999, 3)]);
+        assert_eq!(grid.get_neighbors(5, 123_456_789), Some(0));
+    }
+
+    #[test]
+    fn test_with_rule_rejects_large_neighborhoods() {
+        let rule = Rule {
+            neighborhood: Neighborhood::Custom((1..=300).map(|col| (0, col)).collect()),
+            ..Rule::default()
+        };
+        assert!(SparseGrid::new(10, 10).with_rule(rule).is_err());
+    }
+
+    // The sparse grid must remove the same rolls in the same rounds as the dense one
+    #[rstest]
+    fn test_matches_dense_grid(
+        #[values(EdgeMode::Empty, EdgeMode::Roll, EdgeMode::Wrap, EdgeMode::Mirror)]
+        edges: EdgeMode,
+        #[values(Neighborhood::Moore, Neighborhood::Radius(2))] neighborhood: Neighborhood,
+    ) {
+        let input = random_rolls(11, 19, 21, 70);
+        let threshold = if neighborhood == Neighborhood::Moore {
+            4
+        } else {
+            12
+        };
+        let rule = Rule {
+            neighborhood,
+            threshold,
+            edges,
+            ..Rule::default()
+        };
+
+        let mut grid = parse_grid(&mut input.as_bytes())
+            .unwrap()
+            .with_rule(rule.clone())
+            .unwrap();
+        let mut sparse = sparse_from(&input).with_rule(rule).unwrap();
+        assert_eq!(sparse.accessible(), grid.accessible());
+        assert_eq!(sparse.count_accessible(), grid.count_accessible());
+        loop {
+            let removed = grid.remove();
+            assert_eq!(sparse.remove(), removed);
+            assert_eq!(
+                sparse.positions(),
+                grid.rolls().iter_ones().collect::<Vec<_>>()
+            );
+            if removed == 0 {
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use super::{Backend, Grid};
+use std::fmt::Write as _;
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+const RESET: &str = "\x1b[0m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[34m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Step,
+    TogglePlay,
+    Quit,
+}
+
+impl Command {
+    /// Parses a line typed by the user. An empty line steps to the next round.
+    pub fn parse(line: &str) -> Option<Command> {
+        match line.trim() {
+            "" | "s" | "n" => Some(Command::Step),
+            "p" | " " => Some(Command::TogglePlay),
+            "q" => Some(Command::Quit),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum TuiError {
+    Quit,
+    Io(io::Error),
+}
+
+impl From<io::Error> for TuiError {
+    fn from(error: io::Error) -> Self {
+        TuiError::Io(error)
+    }
+}
+
+/// Reads commands line by line on a separate thread, so the animation can keep playing
+/// while waiting for input.
+pub fn spawn_command_reader(input: impl BufRead + Send + 'static) -> Receiver<Command> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in input.lines().map_while(Result::ok) {
+            if let Some(command) = Command::parse(&line)
+                && sender.send(command).is_err()
+            {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Redraws the grid in the terminal after every removal round. Rolls are blue, or yellow if
+/// they will be removed in the next round, and rolls removed in the last round are shown in
+/// red.
+pub struct Tui<W: Write> {
+    out: W,
+    commands: Receiver<Command>,
+    delay: Duration,
+    playing: bool,
+    round: usize,
+    total_removed: usize,
+}
+
+impl<W: Write> Tui<W> {
+    pub fn new(out: W, commands: Receiver<Command>, delay: Duration) -> Tui<W> {
+        Tui {
+            out,
+            commands,
+            delay,
+            playing: false,
+            round: 0,
+            total_removed: 0,
+        }
+    }
+
+    /// Shows the initial grid and waits for the user to start.
+    pub fn start(&mut self, grid: &Grid) -> Result<(), TuiError> {
+        self.draw(grid, &[], "")?;
+        self.wait()
+    }
+
+    /// Shows the grid after a removal round and waits for the next step.
+    pub fn show_round(&mut self, grid: &Grid, removed: &[(usize, usize)]) -> Result<(), TuiError> {
+        self.round += 1;
+        self.total_removed += removed.len();
+        self.draw(grid, removed, "")?;
+        self.wait()
+    }
+
+    /// Shows the final grid once no more rolls can be removed.
+    pub fn finish(&mut self, grid: &Grid) -> io::Result<()> {
+        self.draw(grid, &[], "No more rolls can be removed.")
+    }
+
+    fn draw(&mut self, grid: &Grid, removed: &[(usize, usize)], message: &str) -> io::Result<()> {
+        let cols = grid.cols();
+        let mut cells = vec![Cell::Empty; grid.rows() * cols];
+        for &(row, col) in removed {
+            cells[row * cols + col] = Cell::Removed;
+        }
+        for ((row, col), count) in grid.neighbor_counts() {
+            cells[row * cols + col] = if grid.rule().is_accessible(count) {
+                Cell::Accessible
+            } else {
+                Cell::Roll
+            };
+        }
+
+        let mut screen = String::from(CLEAR_SCREEN);
+        let _ = writeln!(
+            screen,
+            "Round {}: {} removed, {} removed in total, {} rolls left",
+            self.round,
+            removed.len(),
+            self.total_removed,
+            grid.rolls().count_ones()
+        );
+        let _ = writeln!(
+            screen,
+            "[Enter] step  [p] {}  [q] quit\n",
+            if self.playing { "pause" } else { "play" }
+        );
+
+        for row in cells.chunks(cols.max(1)) {
+            let mut current = None;
+            for &cell in row {
+                if current != Some(cell) {
+                    screen.push_str(cell.color());
+                    current = Some(cell);
+                }
+                screen.push(cell.symbol());
+            }
+            screen.push_str(RESET);
+            screen.push('\n');
+        }
+        screen.push_str(message);
+        screen.push('\n');
+
+        self.out.write_all(screen.as_bytes())?;
+        self.out.flush()
+    }
+
+    fn wait(&mut self) -> Result<(), TuiError> {
+        loop {
+            let command = if self.playing {
+                match self.commands.recv_timeout(self.delay) {
+                    Ok(command) => command,
+                    Err(RecvTimeoutError::Timeout) => return Ok(()),
+                    Err(RecvTimeoutError::Disconnected) => {
+                        std::thread::sleep(self.delay);
+                        return Ok(());
+                    }
+                }
+            } else {
+                match self.commands.recv() {
+                    Ok(command) => command,
+                    // Without any way to control the animation, just play it
+                    Err(_) => {
+                        self.playing = true;
+                        return Ok(());
+                    }
+                }
+            };
+
+            match command {
+                Command::Quit => return Err(TuiError::Quit),
+                Command::TogglePlay => {
+                    self.playing = !self.playing;
+                    if self.playing {
+                        return Ok(());
+                    }
+                }
+                Command::Step if !self.playing => return Ok(()),
+                Command::Step => {}
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Empty,
+    Roll,
+    Accessible,
+    Removed,
+}
+
+impl Cell {
+    fn symbol(&self) -> char {
+        match self {
+            Cell::Empty => '.',
+            Cell::Roll | Cell::Accessible => '@',
+            Cell::Removed => 'x',
+        }
+    }
+
+    fn color(&self) -> &'static str {
+        match self {
+            Cell::Empty => DIM,
+            Cell::Roll => BLUE,
+            Cell::Accessible => YELLOW,
+            Cell::Removed => RED,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day4::parse_grid;
+
+    fn strip_ansi(output: &[u8]) -> String {
+        let text = String::from_utf8_lossy(output);
+        let mut plain = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c == '\x1b' {
+                chars.by_ref().find(|c| c.is_ascii_alphabetic());
+            } else {
+                plain.push(c);
+            }
+        }
+        plain
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(Command::parse(""), Some(Command::Step));
+        assert_eq!(Command::parse("p\r"), Some(Command::TogglePlay));
+        assert_eq!(Command::parse("q"), Some(Command::Quit));
+        assert_eq!(Command::parse("x"), None);
+    }
+
+    #[test]
+    fn test_steps_through_rounds() {
+        let mut grid = parse_grid(&mut "@@@@@\n@@@@@\n@@@@@\n".as_bytes()).unwrap();
+        let commands = spawn_command_reader(io::Cursor::new("\n\n\n\n\n\n"));
+        let mut out = Vec::new();
+        let mut tui = Tui::new(&mut out, commands, Duration::ZERO);
+
+        tui.start(&grid).unwrap();
+        let history = grid
+            .remove_all_with(|grid, removed| tui.show_round(grid, removed))
+            .unwrap();
+        tui.finish(&grid).unwrap();
+
+        assert_eq!(history.total_removed(), 15);
+        let screen = strip_ansi(&out);
+        assert!(screen.contains("Round 0: 0 removed, 0 removed in total, 15 rolls left"));
+        assert!(screen.contains("Round 1: 4 removed, 4 removed in total, 11 rolls left"));
+        assert!(screen.contains("x@@@x\n@@@@@\nx@@@x\n"));
+        assert!(screen.contains("Round 5: 1 removed, 15 removed in total, 0 rolls left"));
+        assert!(screen.ends_with("No more rolls can be removed.\n"));
+    }
+
+    #[test]
+    fn test_quit_stops_removal() {
+        let mut grid = parse_grid(&mut "@@@@@\n@@@@@\n@@@@@\n".as_bytes()).unwrap();
+        let commands = spawn_command_reader(io::Cursor::new("\nq\n"));
+        let mut tui = Tui::new(io::sink(), commands, Duration::ZERO);
+
+        tui.start(&grid).unwrap();
+        let result = grid.remove_all_with(|grid, removed| tui.show_round(grid, removed));
+
+        assert!(matches!(result, Err(TuiError::Quit)));
+        assert_eq!(grid.rolls().count_ones(), 11);
+    }
+
+    #[test]
+    fn test_plays_without_commands() {
+        let mut grid = parse_grid(&mut "@@@\n@@@\n".as_bytes()).unwrap();
+        let commands = spawn_command_reader(io::Cursor::new(""));
+        let mut tui = Tui::new(io::sink(), commands, Duration::ZERO);
+
+        tui.start(&grid).unwrap();
+        let history = grid
+            .remove_all_with(|grid, removed| tui.show_round(grid, removed))
+            .unwrap();
+
+        assert_eq!(history.total_removed(), 6);
+    }
+}
@@ -1,51 +1,130 @@
+use aoc2025::Part;
+use aoc2025::day5::{self, ParseError, RangeIndex};
+use aoc2025::interval_set::IntervalSet;
 use std::io::BufRead;
 use std::ops::RangeInclusive;
 use std::time::Instant;
 
 fn main() {
+    let options = match day5::Options::parse(Part::One, std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, day5::USAGE);
+            std::process::exit(2);
+        }
+    };
+
     let mut handle = std::io::stdin().lock();
+    let open = |path| day5::open_section(path).unwrap_or_else(|error| exit_invalid_input(error));
+    let mut ranges_file = options.ranges.as_deref().map(open);
+    let mut ingredients_file = options.ingredients.as_deref().map(open);
+
+    // Sections that are not read from a file come from standard input, in order
+    let (ranges, ingredients): (&mut dyn BufRead, Option<&mut dyn BufRead>) =
+        match (&mut ranges_file, &mut ingredients_file) {
+            (Some(ranges), Some(ingredients)) => (ranges, Some(ingredients)),
+            (Some(ranges), None) => (ranges, Some(&mut handle)),
+            (None, Some(ingredients)) => (&mut handle, Some(ingredients)),
+            (None, None) => (&mut handle, None),
+        };
+
+    if let Some(format) = options.report {
+        let sources = match ingredients {
+            Some(_) => day5::parse_ranges(ranges),
+            None => day5::parse_ranges_before_ingredients(ranges),
+        };
+        let sources = sources.unwrap_or_else(|error| exit_invalid_input(error));
+        let index = RangeIndex::new(sources);
+        let mut read_error = None;
+        let ingredients =
+            parse_available_ingredients(ingredients.unwrap_or(ranges), &mut read_error);
+        let mut out = std::io::stdout().lock();
+        if let Err(error) = day5::write_report(&mut out, format, &index, ingredients) {
+            eprintln!("Could not write the report: {}", error);
+            std::process::exit(1);
+        }
+        if let Some(error) = read_error {
+            exit_invalid_input(error);
+        }
+        return;
+    }
 
     let now = Instant::now();
-    let result = solve(&mut handle);
+    let result = solve(ranges, ingredients, options.progress);
     let elapsed = now.elapsed();
 
-    println!("{}", result);
+    match result {
+        Ok(result) => println!("{}", result),
+        Err(error) => exit_invalid_input(error),
+    }
     eprintln!("Elapsed: {:.2?}", elapsed);
 }
 
-fn solve(reader: &mut dyn BufRead) -> i32 {
-    let fresh_ranges = parse_fresh_ingredient_ranges(reader);
+// The ingredients follow the ranges in `reader` unless they are read from a separate reader.
+// They are checked as they are read, so the list can be longer than fits in memory.
+fn solve<'a>(
+    reader: &'a mut dyn BufRead,
+    ingredients: Option<&'a mut dyn BufRead>,
+    progress: Option<u64>,
+) -> Result<u64, ParseError> {
+    let fresh: IntervalSet = parse_fresh_ingredient_ranges(reader, ingredients.is_none())?
+        .into_iter()
+        .collect();
 
-    parse_available_ingredients(reader)
-        .filter(|ingredient| fresh_ranges.iter().any(|range| range.contains(ingredient)))
-        .count() as i32
+    let mut read_error = None;
+    let ingredients = parse_available_ingredients(ingredients.unwrap_or(reader), &mut read_error);
+    let tally = day5::tally_fresh(&fresh, ingredients, progress, |tally| {
+        eprintln!(
+            "{} ingredients checked, {} fresh",
+            tally.checked, tally.fresh
+        )
+    });
+    match read_error {
+        Some(error) => Err(error),
+        None => Ok(tally.fresh),
+    }
 }
 
-fn parse_fresh_ingredient_ranges(reader: &mut dyn BufRead) -> Vec<RangeInclusive<u64>> {
-    let mut fresh_ranges: Vec<RangeInclusive<u64>> = Vec::new();
-
-    for line in reader.lines().filter_map(|line| line.ok()) {
-        if line.is_empty() {
-            break;
-        }
-
-        match line.split_once('-') {
-            Some((start, end)) => match (start.parse(), end.parse()) {
-                (Ok(start), Ok(end)) => fresh_ranges.push(start..=end),
-                _ => continue,
-            },
-            None => continue,
-        };
-    }
+fn parse_fresh_ingredient_ranges(
+    reader: &mut dyn BufRead,
+    ingredients_follow: bool,
+) -> Result<Vec<RangeInclusive<u64>>, ParseError> {
+    let ranges = if ingredients_follow {
+        day5::parse_ranges_before_ingredients(reader)?
+    } else {
+        day5::parse_ranges(reader)?
+    };
+    Ok(ranges.into_iter().map(|source| source.range).collect())
+}
 
-    fresh_ranges
+fn exit_invalid_input(error: ParseError) -> ! {
+    eprintln!("Invalid input: {}", error);
+    std::process::exit(1);
 }
 
-fn parse_available_ingredients(reader: &mut dyn BufRead) -> impl Iterator<Item = u64> {
+// Blank lines are skipped. Reading stops at the first line that cannot be read, for example
+// because it is not valid UTF-8, or that is not an ID, and the error is stored in `error`.
+fn parse_available_ingredients<'a>(
+    reader: &'a mut dyn BufRead,
+    error: &'a mut Option<ParseError>,
+) -> impl Iterator<Item = u64> + 'a {
     reader
         .lines()
-        .filter_map(|line| line.ok())
-        .filter_map(|line| line.trim().parse::<u64>().ok())
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+        .map_while(|(index, line)| {
+            let result = line.map_err(ParseError::from).and_then(|line| {
+                line.trim()
+                    .parse::<u64>()
+                    .map_err(|_| ParseError::InvalidIngredient {
+                        line: index + 1,
+                        text: line,
+                    })
+            });
+            result
+                .map_err(|parse_error| *error = Some(parse_error))
+                .ok()
+        })
 }
 
 #[cfg(test)]
@@ -57,7 +136,85 @@ mod tests {
     fn test_example() {
         let input = "3-5\n10-14\n16-20\n12-18\n\n1\n5\n8\n11\n17\n32\n";
         let mut reader = Cursor::new(input);
-        let result = solve(&mut reader);
+        let result = solve(&mut reader, None, None).unwrap();
+        assert_eq!(result, 3);
+    }
+
+    #[test]
+    fn test_example_with_sorted_ingredients() {
+        let input = "3-5\n10-14\n16-20\n12-18\n\n1\n5\n5\n8\n11\n17\n32\n";
+        let mut reader = Cursor::new(input);
+        let result = solve(&mut reader, None, None).unwrap();
+        assert_eq!(result, 4);
+    }
+
+    #[test]
+    fn test_largest_id() {
+        let input = "18446744073709551614-18446744073709551615\n\n18446744073709551615\n0\n";
+        let mut reader = Cursor::new(input);
+        assert_eq!(solve(&mut reader, None, None).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_range_syntax() {
+        let input = "3..6 # half-open\n 10 - 14\n16-\n# comment\n12\n\n1\n5\n8\n11\n12\n17\n32\n";
+        let mut reader = Cursor::new(input);
+        assert_eq!(solve(&mut reader, None, None).unwrap(), 5);
+    }
+
+    #[test]
+    fn test_invalid_range_is_an_error() {
+        let mut reader = Cursor::new("3-5\n14-10\n\n4\n");
+        let error = solve(&mut reader, None, None).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: invalid range \"14-10\", the start 14 is after the end 10"
+        );
+    }
+
+    #[test]
+    fn test_whitespace_and_crlf_separators() {
+        for input in [
+            "3-5\n10-14\n16-20\n12-18\n  \n1\n5\n8\n11\n17\n32\n",
+            "3-5\r\n10-14\r\n16-20\r\n12-18\r\n\r\n1\r\n5\r\n8\r\n11\r\n17\r\n32\r\n",
+        ] {
+            let mut reader = Cursor::new(input);
+            assert_eq!(solve(&mut reader, None, None).unwrap(), 3, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_unreadable_ingredient_is_an_error() {
+        let mut reader = Cursor::new(b"3-5\n\n4\n\xff\n5\n".as_slice());
+        let error = solve(&mut reader, None, None).unwrap_err();
+        assert!(matches!(error, ParseError::Io(_)), "{:?}", error);
+    }
+
+    #[test]
+    fn test_named_sections() {
+        let input =
+            "[ranges]\n3-5\n10-14\n\n16-20\n12-18\n\n[ingredients]\n1\n5\n8\n\n11\n17\n32\n";
+        let mut reader = Cursor::new(input);
+        assert_eq!(solve(&mut reader, None, None).unwrap(), 3);
+
+        let mut reader = Cursor::new("[ingredients]\n5\n[ranges]\n3-5\n");
+        assert!(solve(&mut reader, None, None).is_err());
+
+        let mut reader = Cursor::new("[ranges]\n3-5\n10-14\n\n1\n5\n11\n");
+        let error = solve(&mut reader, None, None).unwrap_err();
+        assert!(matches!(error, ParseError::MissingIngredients));
+
+        let mut ranges = Cursor::new("[ranges]\n3-5\n10-14\n");
+        let mut ingredients = Cursor::new("1\n5\n11\n");
+        let result = solve(&mut ranges, Some(&mut ingredients), None).unwrap();
+        assert_eq!(result, 2);
+    }
+
+    #[test]
+    fn test_separate_sections() {
+        let mut ranges = Cursor::new("3-5\n10-14\n16-20\n12-18\n");
+        let mut ingredients = Cursor::new("1\n5\n8\n11\n17\n32\n");
+        let result = solve(&mut ranges, Some(&mut ingredients), None).unwrap();
         assert_eq!(result, 3);
     }
 
@@ -65,7 +222,7 @@ mod tests {
     fn test_input_from_file() {
         let input = include_str!("input.txt");
         let mut reader = input.as_bytes();
-        let result = solve(&mut reader);
+        let result = solve(&mut reader, None, None).unwrap();
         assert_eq!(result, 733);
     }
 
@@ -73,7 +230,7 @@ mod tests {
     fn test_parse_fresh_ingredient_ranges_no_input_returns_empty() {
         let input = "";
         let mut reader = Cursor::new(input);
-        let result = parse_fresh_ingredient_ranges(&mut reader);
+        let result = parse_fresh_ingredient_ranges(&mut reader, true).unwrap();
         assert_eq!(result, vec![]);
     }
 
@@ -81,7 +238,7 @@ mod tests {
     fn test_parse_fresh_ingredient_ranges_with_input() {
         let input = "1-3\n5-7\n10-15\n";
         let mut reader = Cursor::new(input);
-        let result = parse_fresh_ingredient_ranges(&mut reader);
+        let result = parse_fresh_ingredient_ranges(&mut reader, true).unwrap();
         assert_eq!(result, vec![1..=3, 5..=7, 10..=15]);
     }
 
@@ -89,15 +246,27 @@ mod tests {
     fn test_parse_available_ingredients() {
         let input = "4\n8\n15\n16\n23\n42\n";
         let mut reader = Cursor::new(input);
-        let result: Vec<u64> = parse_available_ingredients(&mut reader).collect();
+        let result: Vec<u64> = parse_available_ingredients(&mut reader, &mut None).collect();
         assert_eq!(result, vec![4, 8, 15, 16, 23, 42]);
     }
 
     #[test]
     fn test_parse_available_ingredients_with_invalid_lines() {
-        let input = "4\ninvalid\n15\n16\nnot_a_number\n42\n";
+        let input = "4\n\n15\n16\nnot_a_number\n42\n";
         let mut reader = Cursor::new(input);
-        let result: Vec<u64> = parse_available_ingredients(&mut reader).collect();
-        assert_eq!(result, vec![4, 15, 16, 42]);
+        let mut error = None;
+        let result: Vec<u64> = parse_available_ingredients(&mut reader, &mut error).collect();
+        assert_eq!(result, vec![4, 15, 16]);
+        assert_eq!(
+            error.unwrap().to_string(),
+            "line 5 of the ingredients: expected an ingredient ID, found \"not_a_number\""
+        );
+
+        let mut reader = Cursor::new("3-5\n\n4\ninvalid\n");
+        let error = solve(&mut reader, None, None).unwrap_err();
+        assert!(matches!(
+            error,
+            ParseError::InvalidIngredient { line: 2, .. }
+        ));
     }
 }
@@ -1,69 +1,105 @@
+use aoc2025::Part;
+use aoc2025::day5::{self, ParseError};
+use aoc2025::interval_set::IntervalSet;
 use std::io::BufRead;
 use std::ops::RangeInclusive;
 use std::time::Instant;
 
 fn main() {
+    let options = match day5::Options::parse(Part::Two, std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, day5::USAGE);
+            std::process::exit(2);
+        }
+    };
+
     let mut handle = std::io::stdin().lock();
+    let mut ranges_file = options
+        .ranges
+        .as_deref()
+        .map(|path| day5::open_section(path).unwrap_or_else(|error| exit_invalid_input(error)));
+    // The ingredients follow the ranges on standard input, but not in a file of ranges
+    let ingredients_follow = ranges_file.is_none();
+    let reader: &mut dyn BufRead = match &mut ranges_file {
+        Some(file) => file,
+        None => &mut handle,
+    };
+
+    if options.operations {
+        let operations =
+            day5::parse_operations(reader).unwrap_or_else(|error| exit_invalid_input(error));
+        let mut fresh = IntervalSet::new();
+        for operation in operations {
+            println!("{}", operation.apply(&mut fresh));
+        }
+        return;
+    }
+
+    if options.coverage {
+        let coverage = day5::Coverage::new(read_ranges(reader, ingredients_follow));
+        for (depth, count) in coverage.depth_counts().iter().enumerate().skip(1) {
+            println!("{}: {}", depth, count);
+        }
+        return;
+    }
+
+    if let Some(bounds) = options.gaps {
+        let fresh: IntervalSet = read_ranges(reader, ingredients_follow)
+            .into_iter()
+            .collect();
+        let spoiled = fresh.complement(bounds);
+        println!("{}", spoiled.len());
+        for gap in day5::largest_gaps(&spoiled, options.largest) {
+            println!(
+                "{}-{} ({} IDs)",
+                gap.start(),
+                gap.end(),
+                (gap.end() - gap.start()) as u128 + 1
+            );
+        }
+        return;
+    }
 
     let now = Instant::now();
-    let result = solve(&mut handle);
+    let result = solve(reader, ingredients_follow);
     let elapsed = now.elapsed();
 
-    println!("{}", result);
+    match result {
+        Ok(result) => println!("{}", result),
+        Err(error) => exit_invalid_input(error),
+    }
     eprintln!("Elapsed: {:.2?}", elapsed);
 }
 
-fn solve(reader: &mut dyn BufRead) -> u64 {
-    let fresh_ranges = parse_fresh_ingredient_ranges(reader);
-    let merged_ranges: Vec<RangeInclusive<u64>> = merge_ranges(&fresh_ranges);
+fn solve(reader: &mut dyn BufRead, ingredients_follow: bool) -> Result<u128, ParseError> {
+    let fresh: IntervalSet = parse_fresh_ingredient_ranges(reader, ingredients_follow)?
+        .into_iter()
+        .collect();
 
-    merged_ranges
-        .iter()
-        .map(|range| range.end() - range.start() + 1)
-        .sum()
+    Ok(fresh.len())
 }
 
-fn merge_ranges(ranges: &Vec<RangeInclusive<u64>>) -> Vec<RangeInclusive<u64>> {
-    if ranges.is_empty() {
-        return vec![];
-    }
-
-    let mut sorted = ranges.to_vec();
-    sorted.sort_by_key(|r| *r.start());
-
-    let mut merged = vec![sorted[0].clone()];
-
-    for range in sorted.iter().skip(1) {
-        let last = merged.last_mut().unwrap();
-
-        if *range.start() <= *last.end() + 1 {
-            *last = *last.start()..=(*last.end()).max(*range.end());
-        } else {
-            merged.push(range.clone());
-        }
-    }
-
-    merged
+fn parse_fresh_ingredient_ranges(
+    reader: &mut dyn BufRead,
+    ingredients_follow: bool,
+) -> Result<Vec<RangeInclusive<u64>>, ParseError> {
+    let ranges = if ingredients_follow {
+        day5::parse_ranges_before_ingredients(reader)?
+    } else {
+        day5::parse_ranges(reader)?
+    };
+    Ok(ranges.into_iter().map(|source| source.range).collect())
 }
 
-fn parse_fresh_ingredient_ranges(reader: &mut dyn BufRead) -> Vec<RangeInclusive<u64>> {
-    let mut fresh_ranges: Vec<RangeInclusive<u64>> = Vec::new();
-
-    for line in reader.lines().filter_map(|line| line.ok()) {
-        if line.is_empty() {
-            break;
-        }
-
-        match line.split_once('-') {
-            Some((start, end)) => match (start.parse(), end.parse()) {
-                (Ok(start), Ok(end)) => fresh_ranges.push(start..=end),
-                _ => continue,
-            },
-            None => continue,
-        };
-    }
+fn read_ranges(reader: &mut dyn BufRead, ingredients_follow: bool) -> Vec<RangeInclusive<u64>> {
+    parse_fresh_ingredient_ranges(reader, ingredients_follow)
+        .unwrap_or_else(|error| exit_invalid_input(error))
+}
 
-    fresh_ranges
+fn exit_invalid_input(error: ParseError) -> ! {
+    eprintln!("Invalid input: {}", error);
+    std::process::exit(1);
 }
 
 #[cfg(test)]
@@ -75,15 +111,72 @@ mod tests {
     fn test_example() {
         let input = "3-5\n10-14\n16-20\n12-18\n\n1\n5\n8\n11\n17\n32\n";
         let mut reader = Cursor::new(input);
-        let result = solve(&mut reader);
+        let result = solve(&mut reader, true).unwrap();
         assert_eq!(result, 14);
     }
 
+    #[test]
+    fn test_ranges_up_to_the_largest_id() {
+        let input = "18446744073709551610-18446744073709551615\n0-0\n\n";
+        let mut reader = Cursor::new(input);
+        assert_eq!(solve(&mut reader, true).unwrap(), 7);
+
+        let input = "0-18446744073709551615\n3-5\n";
+        let mut reader = Cursor::new(input);
+        assert_eq!(solve(&mut reader, true).unwrap(), 1 << 64);
+    }
+
+    #[test]
+    fn test_range_syntax() {
+        let input = "3..6\n10 - 14 # two weeks\n12\n18446744073709551610-\n";
+        let mut reader = Cursor::new(input);
+        assert_eq!(solve(&mut reader, true).unwrap(), 3 + 5 + 6);
+    }
+
+    #[test]
+    fn test_invalid_range_is_an_error() {
+        for input in ["3-5\nfive\n", "4..4\n", "-3\n"] {
+            let mut reader = Cursor::new(input);
+            assert!(
+                solve(&mut reader, true).is_err(),
+                "{:?} was accepted",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_sections() {
+        for input in [
+            "3-5\n10-14\n \n16-20\n",
+            "3-5\r\n10-14\r\n\r\n16-20\r\n",
+            "[ranges]\n3-5\n\n10-14\n[ingredients]\n16-20\n",
+        ] {
+            let mut reader = Cursor::new(input);
+            assert_eq!(solve(&mut reader, true).unwrap(), 8, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn test_named_sections() {
+        let input = "[ranges]\n3-5\n10-14\n\n[ingredients]\n1\n5\n11\n";
+        let mut reader = Cursor::new(input);
+        assert_eq!(solve(&mut reader, true).unwrap(), 8);
+
+        let mut reader = Cursor::new("[ranges]\n3-5\n10-14\n\n1\n5\n11\n");
+        let error = solve(&mut reader, true).unwrap_err();
+        assert!(matches!(error, ParseError::MissingIngredients));
+
+        // A file of ranges holds no ingredients, so it needs no [ingredients] header
+        let mut reader = Cursor::new("[ranges]\n3-5\n10-14\n");
+        assert_eq!(solve(&mut reader, false).unwrap(), 8);
+    }
+
     #[test]
     fn test_input_from_file() {
         let input = include_str!("input.txt");
         let mut reader = input.as_bytes();
-        let result = solve(&mut reader);
+        let result = solve(&mut reader, true).unwrap();
         assert_eq!(result, 345821388687084);
     }
 
@@ -91,7 +184,7 @@ mod tests {
     fn test_parse_fresh_ingredient_ranges_no_input_returns_empty() {
         let input = "";
         let mut reader = Cursor::new(input);
-        let result = parse_fresh_ingredient_ranges(&mut reader);
+        let result = parse_fresh_ingredient_ranges(&mut reader, true).unwrap();
         assert_eq!(result, vec![]);
     }
 
@@ -99,14 +192,15 @@ mod tests {
     fn test_parse_fresh_ingredient_ranges_with_input() {
         let input = "1-3\n5-7\n10-15\n";
         let mut reader = Cursor::new(input);
-        let result = parse_fresh_ingredient_ranges(&mut reader);
+        let result = parse_fresh_ingredient_ranges(&mut reader, true).unwrap();
         assert_eq!(result, vec![1..=3, 5..=7, 10..=15]);
     }
 
     #[test]
     fn test_merge_ranges() {
         let ranges = vec![1..=3, 2..=5, 10..=12, 11..=15];
-        let result = merge_ranges(&ranges);
+        let result: Vec<RangeInclusive<u64>> =
+            ranges.into_iter().collect::<IntervalSet>().iter().collect();
         assert_eq!(result, vec![1..=5, 10..=15]);
     }
 }
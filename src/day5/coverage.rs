@@ -0,0 +1,153 @@
+use std::ops::RangeInclusive;
+
+/// How many of a list of ranges cover each ID, as sorted segments of IDs with the same
+/// coverage depth. IDs covered by no range are not part of any segment.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Coverage {
+    // Sorted by start, adjacent segments have different depths
+    segments: Vec<(u64, u64, usize)>,
+}
+
+impl Coverage {
+    pub fn new(ranges: impl IntoIterator<Item = RangeInclusive<u64>>) -> Coverage {
+        // A range is entered at its start and left right after its end, which is beyond
+        // `u64::MAX` for ranges ending there
+        let mut events: Vec<(u128, isize)> = Vec::new();
+        for range in ranges.into_iter().filter(|range| !range.is_empty()) {
+            events.push((*range.start() as u128, 1));
+            events.push((*range.end() as u128 + 1, -1));
+        }
+        events.sort_unstable();
+
+        let mut segments: Vec<(u64, u64, usize)> = Vec::new();
+        // Ranges are left before others are entered at the same position, so the depth never
+        // drops below 0
+        let mut depth: usize = 0;
+        for (index, &(position, change)) in events.iter().enumerate() {
+            depth = depth.wrapping_add_signed(change);
+            let Some(&(next, _)) = events.get(index + 1) else {
+                break;
+            };
+            if depth == 0 || next == position {
+                continue;
+            }
+
+            let (start, end) = (position as u64, (next - 1) as u64);
+            match segments.last_mut() {
+                Some(last) if last.2 == depth && last.1 as u128 + 1 == position => last.1 = end,
+                _ => segments.push((start, end, depth)),
+            }
+        }
+
+        Coverage { segments }
+    }
+
+    /// Iterates over the covered segments in ascending order, together with the number of
+    /// ranges covering them.
+    pub fn segments(&self) -> impl Iterator<Item = (RangeInclusive<u64>, usize)> + '_ {
+        self.segments
+            .iter()
+            .map(|&(start, end, depth)| (start..=end, depth))
+    }
+
+    /// Returns the number of IDs covered by at least `depth` ranges.
+    pub fn count_at_least(&self, depth: usize) -> u128 {
+        self.segments
+            .iter()
+            .filter(|segment| segment.2 >= depth.max(1))
+            .map(|&(start, end, _)| (end - start) as u128 + 1)
+            .sum()
+    }
+
+    /// Returns the number of IDs covered by at least `depth` ranges for every depth up to
+    /// [`Coverage::max_depth`], indexed by the depth. Like [`Coverage::count_at_least`], depth
+    /// 0 only counts covered IDs.
+    pub fn depth_counts(&self) -> Vec<u128> {
+        // Count the IDs at each exact depth, then add up the deeper ones
+        let mut counts = vec![0u128; self.max_depth() + 1];
+        for &(start, end, depth) in self.segments.iter() {
+            counts[depth] += (end - start) as u128 + 1;
+        }
+        for depth in (1..counts.len().saturating_sub(1)).rev() {
+            counts[depth] += counts[depth + 1];
+        }
+        counts[0] = counts.get(1).copied().unwrap_or(0);
+        counts
+    }
+
+    /// Returns the largest number of ranges covering the same ID, 0 if there are no ranges.
+    pub fn max_depth(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| segment.2)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example() {
+        let coverage = Coverage::new([3..=5, 10..=14, 16..=20, 12..=18]);
+        assert_eq!(
+            coverage.segments().collect::<Vec<_>>(),
+            vec![
+                (3..=5, 1),
+                (10..=11, 1),
+                (12..=14, 2),
+                (15..=15, 1),
+                (16..=18, 2),
+                (19..=20, 1)
+            ]
+        );
+        assert_eq!(coverage.max_depth(), 2);
+        assert_eq!(coverage.count_at_least(0), 14);
+        assert_eq!(coverage.count_at_least(1), 14);
+        assert_eq!(coverage.count_at_least(2), 6);
+        assert_eq!(coverage.count_at_least(3), 0);
+    }
+
+    // Touching ranges of the same depth form one segment, empty ranges are ignored
+    #[test]
+    fn test_merges_touching_segments() {
+        let coverage = Coverage::new([1..=3, 4..=6, 2..=2, 5..=5, RangeInclusive::new(9, 8)]);
+        assert_eq!(
+            coverage.segments().collect::<Vec<_>>(),
+            vec![(1..=1, 1), (2..=2, 2), (3..=4, 1), (5..=5, 2), (6..=6, 1)]
+        );
+        assert_eq!(Coverage::new([]).max_depth(), 0);
+        assert_eq!(Coverage::new([]).count_at_least(1), 0);
+        assert_eq!(Coverage::new([]).depth_counts(), vec![0]);
+    }
+
+    #[test]
+    fn test_depth_matches_scan() {
+        let ranges = [0..=3, 2..=2, 5..=9, 1..=6, 20..=25, 11..=11, 8..=14, 2..=9];
+        let coverage = Coverage::new(ranges.iter().cloned());
+        for k in 0..=5 {
+            let expected = (0..30)
+                .filter(|id| ranges.iter().filter(|range| range.contains(id)).count() >= k.max(1))
+                .count();
+            assert_eq!(coverage.count_at_least(k), expected as u128, "depth {}", k);
+        }
+        assert_eq!(coverage.max_depth(), 4);
+        let counts: Vec<u128> = (0..=4).map(|k| coverage.count_at_least(k)).collect();
+        assert_eq!(coverage.depth_counts(), counts);
+    }
+
+    #[test]
+    fn test_largest_id() {
+        let max = u64::MAX;
+        let coverage = Coverage::new([0..=max, max - 1..=max, max..=max]);
+        assert_eq!(
+            coverage.segments().collect::<Vec<_>>(),
+            vec![(0..=max - 2, 1), (max - 1..=max - 1, 2), (max..=max, 3)]
+        );
+        assert_eq!(coverage.count_at_least(1), 1 << 64);
+        assert_eq!(coverage.count_at_least(3), 1);
+        assert_eq!(coverage.depth_counts(), vec![1 << 64, 1 << 64, 2, 1]);
+    }
+}
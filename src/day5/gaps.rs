@@ -0,0 +1,45 @@
+use super::parse::parse_range;
+use crate::interval_set::IntervalSet;
+use std::ops::RangeInclusive;
+
+/// Returns the `count` largest gaps of `spoiled`, largest first. Gaps of the same size are
+/// ordered by their start.
+pub fn largest_gaps(spoiled: &IntervalSet, count: usize) -> Vec<RangeInclusive<u64>> {
+    let mut gaps: Vec<RangeInclusive<u64>> = spoiled.iter().collect();
+    gaps.sort_by_key(|gap| std::cmp::Reverse(gap.end() - gap.start()));
+    gaps.truncate(count);
+    gaps
+}
+
+/// Parses bounds written like a range of the input, e.g. `start-end`.
+pub fn parse_bounds(s: &str) -> Result<RangeInclusive<u64>, String> {
+    parse_range(s).map_err(|error| format!("invalid bounds {:?}, {}", s, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_largest_gaps() {
+        let fresh: IntervalSet = [3..=5, 10..=14, 16..=20, 12..=18, 30..=30]
+            .into_iter()
+            .collect();
+        let spoiled = fresh.complement(0..=40);
+        assert_eq!(spoiled.to_string(), "0-2,6-9,21-29,31-40");
+        assert_eq!(spoiled.len(), 26);
+        assert_eq!(largest_gaps(&spoiled, 2), vec![31..=40, 21..=29]);
+        assert_eq!(largest_gaps(&spoiled, 10).len(), 4);
+        assert_eq!(largest_gaps(&spoiled, 10)[3], 0..=2);
+    }
+
+    #[test]
+    fn test_parse_bounds() {
+        assert_eq!(parse_bounds("0-100"), Ok(0..=100));
+        assert_eq!(parse_bounds("7-7"), Ok(7..=7));
+        assert_eq!(parse_bounds("10..20"), Ok(10..=19));
+        assert_eq!(parse_bounds("10-"), Ok(10..=u64::MAX));
+        assert!(parse_bounds("8-7").is_err());
+        assert!(parse_bounds("-5").is_err());
+    }
+}
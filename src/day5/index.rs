@@ -0,0 +1,208 @@
+use super::parse::SourceRange;
+use crate::interval_set::IntervalSet;
+
+/// Looks up which of the original ranges cover an ID. The ranges are grouped by the merged
+/// interval they belong to, and the ranges of an interval form an interval tree, so a lookup
+/// only visits the ranges that contain the ID and a logarithmic number of others.
+#[derive(Debug, Clone, Default)]
+pub struct RangeIndex {
+    intervals: Vec<Group>,
+    // Sorted by the start of the range, grouped by interval
+    sources: Vec<SourceRange>,
+    // The sources of each group form an implicit binary search tree, rooted at the middle of
+    // the group. This is the largest end of the subtree rooted at each source.
+    max_ends: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Group {
+    start: u64,
+    end: u64,
+    // The sources of the interval are `sources[first..last]`
+    first: usize,
+    last: usize,
+}
+
+impl RangeIndex {
+    pub fn new(ranges: impl IntoIterator<Item = SourceRange>) -> RangeIndex {
+        let mut sources: Vec<SourceRange> = ranges
+            .into_iter()
+            .filter(|source| !source.range.is_empty())
+            .collect();
+        sources.sort_unstable_by_key(|source| (*source.range.start(), source.line));
+
+        let mut intervals: Vec<Group> = Vec::new();
+        for (index, source) in sources.iter().enumerate() {
+            let (start, end) = (*source.range.start(), *source.range.end());
+            match intervals.last_mut() {
+                Some(last) if start <= last.end.saturating_add(1) => {
+                    last.end = last.end.max(end);
+                    last.last = index + 1;
+                }
+                _ => intervals.push(Group {
+                    start,
+                    end,
+                    first: index,
+                    last: index + 1,
+                }),
+            }
+        }
+
+        let mut max_ends = vec![0; sources.len()];
+        for group in intervals.iter() {
+            fill_max_ends(&sources, &mut max_ends, group.first, group.last);
+        }
+
+        RangeIndex {
+            intervals,
+            sources,
+            max_ends,
+        }
+    }
+
+    pub fn is_fresh(&self, id: u64) -> bool {
+        self.group_of(id).is_some()
+    }
+
+    /// Returns the line numbers of all ranges containing `id`, in ascending order.
+    pub fn sources(&self, id: u64) -> Vec<usize> {
+        let Some(group) = self.group_of(id) else {
+            return Vec::new();
+        };
+
+        let mut lines = Vec::new();
+        self.collect_sources(id, group.first, group.last, &mut lines);
+        lines.sort_unstable();
+        lines
+    }
+
+    /// Returns the merged intervals of all ranges.
+    pub fn intervals(&self) -> IntervalSet {
+        self.intervals
+            .iter()
+            .map(|group| group.start..=group.end)
+            .collect()
+    }
+
+    fn group_of(&self, id: u64) -> Option<&Group> {
+        let index = self.intervals.partition_point(|group| group.end < id);
+        self.intervals.get(index).filter(|group| group.start <= id)
+    }
+
+    // Adds the lines of the sources in `first..last` containing `id`, skipping the subtrees
+    // that end before `id` and the sources that start after it
+    fn collect_sources(&self, id: u64, first: usize, last: usize, lines: &mut Vec<usize>) {
+        if first >= last {
+            return;
+        }
+        let middle = first + (last - first) / 2;
+        if self.max_ends[middle] < id {
+            return;
+        }
+
+        self.collect_sources(id, first, middle, lines);
+        let source = &self.sources[middle];
+        if *source.range.start() > id {
+            return;
+        }
+        if *source.range.end() >= id {
+            lines.push(source.line);
+        }
+        self.collect_sources(id, middle + 1, last, lines);
+    }
+}
+
+// Fills in the largest end of the subtree rooted at the middle of `first..last` and returns it
+fn fill_max_ends(sources: &[SourceRange], max_ends: &mut [u64], first: usize, last: usize) -> u64 {
+    if first >= last {
+        return 0;
+    }
+    let middle = first + (last - first) / 2;
+    let left = fill_max_ends(sources, max_ends, first, middle);
+    let right = fill_max_ends(sources, max_ends, middle + 1, last);
+    max_ends[middle] = (*sources[middle].range.end()).max(left).max(right);
+    max_ends[middle]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day5::parse_ranges;
+    use crate::testing::XorShift;
+
+    fn example() -> RangeIndex {
+        RangeIndex::new(parse_ranges(&mut "3-5\n10-14\n16-20\n12-18\n".as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn test_sources() {
+        let index = example();
+        assert_eq!(index.sources(1), Vec::<usize>::new());
+        assert_eq!(index.sources(5), vec![1]);
+        assert_eq!(index.sources(8), Vec::<usize>::new());
+        assert_eq!(index.sources(11), vec![2]);
+        assert_eq!(index.sources(13), vec![2, 4]);
+        assert_eq!(index.sources(17), vec![3, 4]);
+        assert_eq!(index.sources(32), Vec::<usize>::new());
+        assert_eq!(index.intervals().to_string(), "3-5,10-20");
+    }
+
+    // Every ID must be found in exactly the ranges that contain it
+    #[test]
+    fn test_sources_match_scan() {
+        let input = "0-3\n2-2\n5-9\n1-6\n20-25\n11-11\n26-30\n8-14\n22-23\n";
+        let ranges = parse_ranges(&mut input.as_bytes()).unwrap();
+        let index = RangeIndex::new(ranges.clone());
+        for id in 0..35 {
+            let expected: Vec<usize> = ranges
+                .iter()
+                .filter(|source| source.range.contains(&id))
+                .map(|source| source.line)
+                .collect();
+            assert_eq!(index.sources(id), expected, "sources of {}", id);
+            assert_eq!(index.is_fresh(id), !expected.is_empty());
+        }
+    }
+
+    // Many overlapping ranges of one interval, so the tree has several levels
+    #[test]
+    fn test_sources_match_scan_in_one_interval() {
+        let mut random = XorShift::new(44);
+        let ranges: Vec<SourceRange> = (1..=200)
+            .map(|line| {
+                let start = (random.next_u32() % 500) as u64;
+                let len = (random.next_u32() % 60) as u64;
+                SourceRange {
+                    line,
+                    range: start..=start + len,
+                }
+            })
+            .collect();
+        let index = RangeIndex::new(ranges.clone());
+        for id in 0..570 {
+            let expected: Vec<usize> = ranges
+                .iter()
+                .filter(|source| source.range.contains(&id))
+                .map(|source| source.line)
+                .collect();
+            assert_eq!(index.sources(id), expected, "sources of {}", id);
+        }
+    }
+
+    #[test]
+    fn test_largest_id() {
+        let max = u64::MAX;
+        let index = RangeIndex::new([
+            SourceRange {
+                line: 1,
+                range: max - 1..=max,
+            },
+            SourceRange {
+                line: 2,
+                range: max..=max,
+            },
+        ]);
+        assert_eq!(index.sources(max), vec![1, 2]);
+        assert_eq!(index.sources(max - 1), vec![1]);
+    }
+}
@@ -0,0 +1,20 @@
+mod coverage;
+mod gaps;
+mod index;
+mod operations;
+mod options;
+mod parse;
+mod report;
+mod stream;
+
+pub use coverage::Coverage;
+pub use gaps::{largest_gaps, parse_bounds};
+pub use index::RangeIndex;
+pub use operations::{Operation, Outcome, parse_operations};
+pub use options::{Options, USAGE};
+pub use parse::{
+    ParseError, RangeError, SourceRange, open_section, parse_range, parse_ranges,
+    parse_ranges_before_ingredients,
+};
+pub use report::{ReportFormat, write_report};
+pub use stream::{Tally, tally_fresh};
@@ -0,0 +1,130 @@
+use super::parse::{ParseError, parse_range};
+use crate::interval_set::IntervalSet;
+use std::fmt;
+use std::io::BufRead;
+use std::ops::RangeInclusive;
+
+/// A change to the fresh ranges or a question about them, written as `+ 3-5`, `- 4-4` or
+/// `? 4`. Ranges can be written in any syntax of the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    Add(RangeInclusive<u64>),
+    Remove(RangeInclusive<u64>),
+    Query(u64),
+}
+
+/// The answer to an operation: whether the ID is fresh for a query and the number of fresh
+/// IDs after a change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Fresh(bool),
+    Count(u128),
+}
+
+impl Operation {
+    pub fn apply(&self, fresh: &mut IntervalSet) -> Outcome {
+        match self {
+            Operation::Add(range) => fresh.insert(range.clone()),
+            Operation::Remove(range) => fresh.remove(range.clone()),
+            Operation::Query(id) => return Outcome::Fresh(fresh.contains(*id)),
+        }
+        Outcome::Count(fresh.len())
+    }
+
+    fn parse(s: &str) -> Option<Operation> {
+        let (kind, argument) = s.trim().split_once(char::is_whitespace)?;
+        let argument = argument.trim();
+        let range = || parse_range(argument).ok();
+        match kind {
+            "+" => range().map(Operation::Add),
+            "-" => range().map(Operation::Remove),
+            "?" => argument.parse().ok().map(Operation::Query),
+            _ => None,
+        }
+    }
+}
+
+/// Prints `fresh` or `spoiled` for a query and the number of fresh IDs otherwise.
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Fresh(true) => write!(f, "fresh"),
+            Outcome::Fresh(false) => write!(f, "spoiled"),
+            Outcome::Count(count) => write!(f, "{}", count),
+        }
+    }
+}
+
+/// Reads one operation per line. Empty lines are skipped.
+pub fn parse_operations(reader: &mut dyn BufRead) -> Result<Vec<Operation>, ParseError> {
+    let mut operations = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match Operation::parse(&line) {
+            Some(operation) => operations.push(operation),
+            None => {
+                return Err(ParseError::InvalidOperation {
+                    line: index + 1,
+                    text: line,
+                });
+            }
+        }
+    }
+    Ok(operations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_operations() {
+        let input = "+ 3-5\n\n-  4\n? 4\n+ 8..10\n";
+        assert_eq!(
+            parse_operations(&mut input.as_bytes()).unwrap(),
+            vec![
+                Operation::Add(3..=5),
+                Operation::Remove(4..=4),
+                Operation::Query(4),
+                Operation::Add(8..=9)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_operations() {
+        for input in ["+ -3", "* 3-5", "? x", "+ 5-3", "+3-5", "-", "? 3-4"] {
+            let error = parse_operations(&mut input.as_bytes()).unwrap_err();
+            assert!(
+                matches!(error, ParseError::InvalidOperation { line: 1, .. }),
+                "{:?} was accepted",
+                input
+            );
+        }
+        let error = parse_operations(&mut "+ 1-2\n? -1\n".as_bytes()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: expected an operation + RANGE, - RANGE or ? ID, found \"? -1\""
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let input = "+ 3-5\n? 4\n- 4-4\n? 4\n? 5\n+ 10-20\n- 12-18\n? 15\n+ 1-30\n";
+        let mut fresh = IntervalSet::new();
+        let outcomes: Vec<String> = parse_operations(&mut input.as_bytes())
+            .unwrap()
+            .iter()
+            .map(|operation| operation.apply(&mut fresh).to_string())
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                "3", "fresh", "2", "spoiled", "fresh", "13", "6", "spoiled", "30"
+            ]
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use super::gaps::parse_bounds;
+use super::report::ReportFormat;
+use crate::Part;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Options:
+  --ranges FILE      read the ranges from FILE instead of the first section of the input
+  --ingredients FILE read the ingredients from FILE instead of the rest of the input
+                     (part 1 only)
+  --report F         instead of the answer, print for each ingredient whether it is fresh
+                     and the line numbers of the ranges containing it, as csv or json
+                     (part 1 only)
+  --gaps START-END   instead of the answer, print how many IDs between START and END are
+                     in no range and the largest of these spoiled gaps (part 2 only)
+  --largest N        number of gaps printed by --gaps, 10 by default
+  --operations       read a list of operations instead of the puzzle input, one per line:
+                     + RANGE adds a range, - RANGE removes one and ? ID asks
+                     whether an ID is fresh; prints the number of fresh IDs after each
+                     change and fresh or spoiled for each question (part 2 only)
+  --coverage         instead of the answer, print for each K up to the largest number of
+                     overlapping ranges how many IDs are covered by at least K ranges
+                     (part 2 only)
+  --progress N       print the number of ingredients checked and found fresh so far after
+                     every N ingredients (part 1 only)";
+
+// Options that only make sense in one of the parts, which only needs the ingredients or only
+// the ranges
+const PART_ONE_ONLY: &[&str] = &["--ingredients", "--report", "--progress"];
+const PART_TWO_ONLY: &[&str] = &["--gaps", "--largest", "--operations", "--coverage"];
+
+const DEFAULT_LARGEST: usize = 10;
+
+/// Command line options shared by both parts of day 5.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub ranges: Option<PathBuf>,
+    pub ingredients: Option<PathBuf>,
+    pub report: Option<ReportFormat>,
+    pub gaps: Option<RangeInclusive<u64>>,
+    pub largest: usize,
+    pub operations: bool,
+    pub coverage: bool,
+    pub progress: Option<u64>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            ranges: None,
+            ingredients: None,
+            report: None,
+            gaps: None,
+            largest: DEFAULT_LARGEST,
+            operations: false,
+            coverage: false,
+            progress: None,
+        }
+    }
+}
+
+impl Options {
+    /// Parses the options of the given part, rejecting those that only apply to the other one.
+    pub fn parse(part: Part, args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+        let mut options = Options::default();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (other, other_only) = match part {
+                Part::One => (2, PART_TWO_ONLY),
+                Part::Two => (1, PART_ONE_ONLY),
+            };
+            if other_only.contains(&arg.as_str()) {
+                return Err(format!("{} is only supported in part {}", arg, other));
+            }
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--ranges" => options.ranges = Some(PathBuf::from(value()?)),
+                "--ingredients" => options.ingredients = Some(PathBuf::from(value()?)),
+                "--report" => options.report = Some(value()?.parse()?),
+                "--gaps" => options.gaps = Some(parse_bounds(&value()?)?),
+                "--largest" => {
+                    let largest = value()?;
+                    options.largest = largest
+                        .parse()
+                        .map_err(|_| format!("invalid number of gaps {:?}", largest))?;
+                }
+                "--operations" => options.operations = true,
+                "--coverage" => options.coverage = true,
+                "--progress" => {
+                    let every = value()?;
+                    options.progress = match every.parse() {
+                        Ok(every) if every > 0 => Some(every),
+                        _ => return Err(format!("invalid progress interval {:?}", every)),
+                    }
+                }
+                _ => return Err(format!("unknown option {:?}", arg)),
+            }
+        }
+
+        let modes = [options.gaps.is_some(), options.operations, options.coverage];
+        if modes.iter().filter(|&&mode| mode).count() > 1 {
+            return Err("only one of --gaps, --operations and --coverage can be used".into());
+        }
+        if options.operations && options.ranges.is_some() {
+            return Err("--operations cannot be combined with --ranges".into());
+        }
+
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn part_one(args: &[&str]) -> Result<Options, String> {
+        Options::parse(Part::One, args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn part_two(args: &[&str]) -> Result<Options, String> {
+        Options::parse(Part::Two, args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_parse_defaults() {
+        assert_eq!(part_one(&[]), Ok(Options::default()));
+        assert_eq!(part_two(&[]), Ok(Options::default()));
+    }
+
+    #[test]
+    fn test_parse_files() {
+        let options = part_one(&["--ranges", "fresh.txt", "--ingredients", "stock.txt"]).unwrap();
+        assert_eq!(options.ranges, Some(PathBuf::from("fresh.txt")));
+        assert_eq!(options.ingredients, Some(PathBuf::from("stock.txt")));
+        assert_eq!(
+            part_two(&["--ranges", "fresh.txt"]).unwrap().ranges,
+            Some(PathBuf::from("fresh.txt"))
+        );
+        assert!(part_one(&["--ranges"]).is_err());
+        assert!(part_two(&["--ranges", "fresh.txt", "--operations"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_report() {
+        assert_eq!(
+            part_one(&["--report", "json"]).unwrap().report,
+            Some(ReportFormat::Json)
+        );
+        assert!(part_one(&["--report"]).is_err());
+        assert!(part_one(&["--report", "xml"]).is_err());
+        assert!(part_one(&["--verbose"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_gaps() {
+        let options = part_two(&["--gaps", "0-100", "--largest", "3"]).unwrap();
+        assert_eq!(options.gaps, Some(0..=100));
+        assert_eq!(options.largest, 3);
+        assert_eq!(part_two(&["--gaps", "1-2"]).unwrap().largest, 10);
+        assert!(part_two(&["--gaps", "5-1"]).is_err());
+        assert!(part_two(&["--largest", "many"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_operations() {
+        assert!(part_two(&["--operations"]).unwrap().operations);
+        assert!(!part_two(&[]).unwrap().operations);
+        assert!(part_two(&["--operations", "--gaps", "0-9"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_coverage() {
+        assert!(part_two(&["--coverage"]).unwrap().coverage);
+        assert!(part_two(&["--coverage", "--operations"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_progress() {
+        assert_eq!(
+            part_one(&["--progress", "1000"]).unwrap().progress,
+            Some(1000)
+        );
+        assert_eq!(part_one(&[]).unwrap().progress, None);
+        assert!(part_one(&["--progress", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_options_of_the_other_part() {
+        assert_eq!(
+            part_two(&["--report", "csv"]),
+            Err("--report is only supported in part 1".to_string())
+        );
+        assert_eq!(
+            part_one(&["--gaps", "0-9"]),
+            Err("--gaps is only supported in part 2".to_string())
+        );
+        for arg in ["--largest", "--operations", "--coverage"] {
+            assert!(part_one(&[arg, "1"]).is_err(), "{} was accepted", arg);
+        }
+        for arg in ["--ingredients", "--progress"] {
+            assert!(part_two(&[arg, "1"]).is_err(), "{} was accepted", arg);
+        }
+    }
+}
@@ -0,0 +1,422 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    Open {
+        path: PathBuf,
+        error: io::Error,
+    },
+    /// A section header that is unknown or out of order.
+    UnexpectedSection {
+        line: usize,
+        text: String,
+    },
+    /// A line of the ranges section that is not a range.
+    InvalidRange {
+        line: usize,
+        text: String,
+        error: RangeError,
+    },
+    /// A line of an operations file that is not an operation.
+    InvalidOperation {
+        line: usize,
+        text: String,
+    },
+    /// A line of the ingredients section that is not an ID. Lines are counted from the start
+    /// of the section.
+    InvalidIngredient {
+        line: usize,
+        text: String,
+    },
+    /// The input ends within a `[ranges]` section, before the ingredients.
+    MissingIngredients,
+}
+
+/// Why a range could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeError {
+    Malformed,
+    /// The start comes after the end.
+    Inverted {
+        start: u64,
+        end: u64,
+    },
+    /// A half-open range `a..a`, which contains no IDs.
+    Empty,
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeError::Malformed => {
+                write!(f, "expected START-END, START..END, START- or a single ID")
+            }
+            RangeError::Inverted { start, end } => {
+                write!(f, "the start {} is after the end {}", start, end)
+            }
+            RangeError::Empty => write!(f, "the half-open range contains no IDs"),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(error) => write!(f, "could not read the input: {}", error),
+            ParseError::Open { path, error } => {
+                write!(f, "could not open {}: {}", path.display(), error)
+            }
+            ParseError::UnexpectedSection { line, text } => write!(
+                f,
+                "line {}: unexpected section {:?}, expected [ranges] followed by [ingredients]",
+                line, text
+            ),
+            ParseError::InvalidRange { line, text, error } => {
+                write!(f, "line {}: invalid range {:?}, {}", line, text, error)
+            }
+            ParseError::InvalidOperation { line, text } => write!(
+                f,
+                "line {}: expected an operation + RANGE, - RANGE or ? ID, found {:?}",
+                line, text
+            ),
+            ParseError::InvalidIngredient { line, text } => write!(
+                f,
+                "line {} of the ingredients: expected an ingredient ID, found {:?}",
+                line, text
+            ),
+            ParseError::MissingIngredients => write!(
+                f,
+                "the input ends without an [ingredients] section after the [ranges] section"
+            ),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io(error) | ParseError::Open { error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(error: io::Error) -> Self {
+        ParseError::Io(error)
+    }
+}
+
+/// A range of fresh ingredient IDs together with the line of the input it was read from,
+/// counting from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceRange {
+    pub line: usize,
+    pub range: RangeInclusive<u64>,
+}
+
+/// Parses a range of IDs written as `a-b` including both ends, `a..b` excluding the end,
+/// `a-` up to the largest ID or a single ID `a`. Whitespace around the IDs is ignored.
+pub fn parse_range(text: &str) -> Result<RangeInclusive<u64>, RangeError> {
+    let id = |text: &str| {
+        text.trim()
+            .parse::<u64>()
+            .map_err(|_| RangeError::Malformed)
+    };
+
+    let (start, end) = if let Some((start, end)) = text.split_once("..") {
+        let (start, end) = (id(start)?, id(end)?);
+        if start > end {
+            return Err(RangeError::Inverted { start, end });
+        }
+        if start == end {
+            return Err(RangeError::Empty);
+        }
+        (start, end - 1)
+    } else if let Some((start, end)) = text.split_once('-') {
+        let start = id(start)?;
+        match end.trim() {
+            "" => (start, u64::MAX),
+            end => (start, id(end)?),
+        }
+    } else {
+        let id = id(text)?;
+        (id, id)
+    };
+
+    if start > end {
+        return Err(RangeError::Inverted { start, end });
+    }
+    Ok(start..=end)
+}
+
+/// Reads the ranges section of the input. Everything after a `#` is a comment, and lines
+/// that only contain a comment are skipped.
+///
+/// The section either ends at the first line that is empty or only contains whitespace, or
+/// it starts with a `[ranges]` header and ends at an `[ingredients]` header, in which case
+/// empty lines within the section are skipped.
+pub fn parse_ranges(reader: &mut dyn BufRead) -> Result<Vec<SourceRange>, ParseError> {
+    read_ranges(reader, false)
+}
+
+/// Like [`parse_ranges`], for input in which the ingredients follow the ranges. A `[ranges]`
+/// section must then end at an `[ingredients]` header, otherwise every ingredient would be
+/// read as a range.
+pub fn parse_ranges_before_ingredients(
+    reader: &mut dyn BufRead,
+) -> Result<Vec<SourceRange>, ParseError> {
+    read_ranges(reader, true)
+}
+
+fn read_ranges(
+    reader: &mut dyn BufRead,
+    ingredients_follow: bool,
+) -> Result<Vec<SourceRange>, ParseError> {
+    let mut ranges = Vec::new();
+    let mut named = false;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            if named {
+                continue;
+            }
+            break;
+        }
+
+        let text = line.split_once('#').map_or(line.as_str(), |(text, _)| text);
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        if let Some(name) = text
+            .strip_prefix('[')
+            .and_then(|text| text.strip_suffix(']'))
+        {
+            match name.trim() {
+                "ranges" if !named && ranges.is_empty() => named = true,
+                "ingredients" if named || !ranges.is_empty() => return Ok(ranges),
+                _ => {
+                    return Err(ParseError::UnexpectedSection {
+                        line: index + 1,
+                        text: line,
+                    });
+                }
+            }
+            continue;
+        }
+
+        match parse_range(text) {
+            Ok(range) => ranges.push(SourceRange {
+                line: index + 1,
+                range,
+            }),
+            Err(error) => {
+                return Err(ParseError::InvalidRange {
+                    line: index + 1,
+                    text: line,
+                    error,
+                });
+            }
+        }
+    }
+
+    if named && ingredients_follow {
+        return Err(ParseError::MissingIngredients);
+    }
+    Ok(ranges)
+}
+
+/// Opens a file holding one section of the input.
+pub fn open_section(path: &Path) -> Result<BufReader<File>, ParseError> {
+    match File::open(path) {
+        Ok(file) => Ok(BufReader::new(file)),
+        Err(error) => Err(ParseError::Open {
+            path: path.to_path_buf(),
+            error,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_parse_ranges_keeps_line_numbers() {
+        let input = "3-5\n# fresh all week\n10-14\n\n1\n";
+        let ranges = parse_ranges(&mut input.as_bytes()).unwrap();
+        assert_eq!(
+            ranges,
+            vec![
+                SourceRange {
+                    line: 1,
+                    range: 3..=5
+                },
+                SourceRange {
+                    line: 3,
+                    range: 10..=14
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_ranges_stops_at_empty_line() {
+        let mut reader = "1-2\n\n3-4\n".as_bytes();
+        assert_eq!(parse_ranges(&mut reader).unwrap().len(), 1);
+        assert_eq!(parse_ranges(&mut "".as_bytes()).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_parse_ranges_whitespace_separator() {
+        for input in ["1-2\n   \n3-4\n", "1-2\r\n\r\n3-4\r\n", "1-2\n\t\n3-4\n"] {
+            let mut reader = input.as_bytes();
+            let ranges = parse_ranges(&mut reader).unwrap();
+            assert_eq!(ranges.len(), 1, "{:?}", input);
+            assert_eq!(ranges[0].range, 1..=2);
+        }
+    }
+
+    #[test]
+    fn test_parse_named_sections() {
+        let input = "# inventory\n[ranges]\n3-5\n\n10-14\n[ingredients]\n4\n";
+        let mut reader = input.as_bytes();
+        let ranges = parse_ranges(&mut reader).unwrap();
+        assert_eq!(
+            ranges.iter().map(|source| source.line).collect::<Vec<_>>(),
+            vec![3, 5]
+        );
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "4\n");
+
+        let mut reader = "1-2\n[ingredients]\n4\n".as_bytes();
+        assert_eq!(parse_ranges(&mut reader).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_named_ranges_without_ingredients() {
+        let input = "[ranges]\n3-5\n10-14\n\n1\n5\n11\n";
+        assert_eq!(parse_ranges(&mut input.as_bytes()).unwrap().len(), 5);
+        let error = parse_ranges_before_ingredients(&mut input.as_bytes()).unwrap_err();
+        assert!(matches!(error, ParseError::MissingIngredients));
+
+        let input = "[ranges]\n3-5\n[ingredients]\n";
+        assert_eq!(
+            parse_ranges_before_ingredients(&mut input.as_bytes())
+                .unwrap()
+                .len(),
+            1
+        );
+        let input = "3-5\n\n4\n";
+        assert_eq!(
+            parse_ranges_before_ingredients(&mut input.as_bytes())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_parse_unexpected_sections() {
+        for input in [
+            "[ingredients]\n4\n[ranges]\n3-5\n",
+            "[spices]\n",
+            "1-2\n[ranges]\n",
+        ] {
+            let error = parse_ranges(&mut input.as_bytes()).unwrap_err();
+            assert!(
+                matches!(error, ParseError::UnexpectedSection { .. }),
+                "{:?} was accepted",
+                input
+            );
+        }
+        let error = parse_ranges(&mut "[ranges]\n[ranges]\n".as_bytes()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: unexpected section \"[ranges]\", expected [ranges] followed by [ingredients]"
+        );
+    }
+
+    #[test]
+    fn test_open_missing_section() {
+        let error = open_section(Path::new("no/such/ranges.txt")).unwrap_err();
+        assert!(matches!(error, ParseError::Open { .. }));
+        assert!(
+            error
+                .to_string()
+                .starts_with("could not open no/such/ranges.txt: ")
+        );
+    }
+
+    #[test]
+    fn test_parse_range_syntax() {
+        assert_eq!(parse_range("3-5"), Ok(3..=5));
+        assert_eq!(parse_range(" 3 - 5 "), Ok(3..=5));
+        assert_eq!(parse_range("3..5"), Ok(3..=4));
+        assert_eq!(parse_range("3..4"), Ok(3..=3));
+        assert_eq!(parse_range("3-"), Ok(3..=u64::MAX));
+        assert_eq!(parse_range("7"), Ok(7..=7));
+        assert_eq!(parse_range("\t7 "), Ok(7..=7));
+        assert_eq!(parse_range("5-5"), Ok(5..=5));
+        assert_eq!(parse_range("0..18446744073709551615"), Ok(0..=u64::MAX - 1));
+    }
+
+    #[test]
+    fn test_parse_range_errors() {
+        for text in [
+            "",
+            "-5",
+            "a-b",
+            "3-5-7",
+            "3..",
+            "..5",
+            "1.5",
+            "3 5",
+            "18446744073709551616",
+        ] {
+            assert_eq!(parse_range(text), Err(RangeError::Malformed), "{:?}", text);
+        }
+        assert_eq!(
+            parse_range("5-3"),
+            Err(RangeError::Inverted { start: 5, end: 3 })
+        );
+        assert_eq!(
+            parse_range("5..3"),
+            Err(RangeError::Inverted { start: 5, end: 3 })
+        );
+        assert_eq!(
+            parse_range("5..0"),
+            Err(RangeError::Inverted { start: 5, end: 0 })
+        );
+        assert_eq!(parse_range("5..5"), Err(RangeError::Empty));
+    }
+
+    #[test]
+    fn test_parse_ranges_reports_line() {
+        let input = "3-5 # comment\n# note\n10-7\n";
+        let error = parse_ranges(&mut input.as_bytes()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 3: invalid range \"10-7\", the start 10 is after the end 7"
+        );
+        let error = parse_ranges(&mut "1-2\nfresh\n".as_bytes()).unwrap_err();
+        assert!(matches!(
+            error,
+            ParseError::InvalidRange {
+                line: 2,
+                error: RangeError::Malformed,
+                ..
+            }
+        ));
+    }
+}
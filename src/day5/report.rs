@@ -0,0 +1,108 @@
+use super::index::RangeIndex;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!(
+                "unknown report format {:?}, expected csv or json",
+                s
+            )),
+        }
+    }
+}
+
+/// Writes whether each ingredient is fresh and the line numbers of the ranges containing it,
+/// in the order of the ingredients.
+///
+/// CSV reports have an `ingredient,fresh,lines` header and separate the line numbers with
+/// spaces. JSON reports are an array with one object per ingredient.
+pub fn write_report(
+    out: &mut dyn Write,
+    format: ReportFormat,
+    index: &RangeIndex,
+    ingredients: impl IntoIterator<Item = u64>,
+) -> io::Result<()> {
+    match format {
+        ReportFormat::Csv => writeln!(out, "ingredient,fresh,lines")?,
+        ReportFormat::Json => write!(out, "[")?,
+    }
+
+    for (position, ingredient) in ingredients.into_iter().enumerate() {
+        let lines: Vec<String> = index
+            .sources(ingredient)
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        let fresh = !lines.is_empty();
+        match format {
+            ReportFormat::Csv => writeln!(out, "{},{},{}", ingredient, fresh, lines.join(" "))?,
+            ReportFormat::Json => {
+                if position > 0 {
+                    write!(out, ",")?;
+                }
+                write!(
+                    out,
+                    "\n  {{\"ingredient\": {}, \"fresh\": {}, \"lines\": [{}]}}",
+                    ingredient,
+                    fresh,
+                    lines.join(", ")
+                )?;
+            }
+        }
+    }
+
+    if format == ReportFormat::Json {
+        writeln!(out, "\n]")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::day5::parse_ranges;
+
+    fn report(format: ReportFormat, ingredients: &[u64]) -> String {
+        let index =
+            RangeIndex::new(parse_ranges(&mut "3-5\n10-14\n16-20\n12-18\n".as_bytes()).unwrap());
+        let mut out = Vec::new();
+        write_report(&mut out, format, &index, ingredients.iter().copied()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(
+            report(ReportFormat::Csv, &[1, 5, 8, 11, 17, 32]),
+            "ingredient,fresh,lines\n1,false,\n5,true,1\n8,false,\n11,true,2\n17,true,3 4\n32,false,\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        assert_eq!(
+            report(ReportFormat::Json, &[1, 17]),
+            "[\n  {\"ingredient\": 1, \"fresh\": false, \"lines\": []},\n  {\"ingredient\": 17, \"fresh\": true, \"lines\": [3, 4]}\n]\n"
+        );
+        assert_eq!(report(ReportFormat::Json, &[]), "[\n]\n");
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("csv".parse(), Ok(ReportFormat::Csv));
+        assert_eq!("json".parse(), Ok(ReportFormat::Json));
+        assert!("xml".parse::<ReportFormat>().is_err());
+    }
+}
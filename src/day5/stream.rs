@@ -0,0 +1,62 @@
+use crate::interval_set::IntervalSet;
+
+/// Running totals of a stream of ingredients.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub checked: u64,
+    pub fresh: u64,
+}
+
+/// Checks the ingredients one at a time as they are read, without keeping them, and calls
+/// `progress` with the totals so far after every `every` ingredients.
+pub fn tally_fresh(
+    fresh: &IntervalSet,
+    ingredients: impl IntoIterator<Item = u64>,
+    every: Option<u64>,
+    mut progress: impl FnMut(Tally),
+) -> Tally {
+    let mut cursor = fresh.cursor();
+    let mut tally = Tally::default();
+    for ingredient in ingredients {
+        tally.checked += 1;
+        if cursor.contains(ingredient) {
+            tally.fresh += 1;
+        }
+        if every.is_some_and(|every| tally.checked.is_multiple_of(every)) {
+            progress(tally);
+        }
+    }
+    tally
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tally_reports_progress() {
+        let fresh: IntervalSet = [3..=5, 10..=14, 16..=20, 12..=18].into_iter().collect();
+        let mut reported = Vec::new();
+        let tally = tally_fresh(&fresh, [1, 5, 8, 11, 17, 32, 3], Some(3), |tally| {
+            reported.push((tally.checked, tally.fresh))
+        });
+        assert_eq!(
+            tally,
+            Tally {
+                checked: 7,
+                fresh: 4
+            }
+        );
+        assert_eq!(reported, vec![(3, 1), (6, 3)]);
+    }
+
+    // The ingredients are never collected, so a stream far larger than the ranges works
+    #[test]
+    fn test_tally_long_stream() {
+        let fresh: IntervalSet = [0..=9, 100..=109].into_iter().collect();
+        let ingredients = (0..1_000_000u64).map(|i| i % 200);
+        let tally = tally_fresh(&fresh, ingredients, None, |_| unreachable!());
+        assert_eq!(tally.checked, 1_000_000);
+        assert_eq!(tally.fresh, 100_000);
+    }
+}
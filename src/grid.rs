@@ -0,0 +1,353 @@
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+/// Offsets of the eight cells surrounding a cell.
+pub const MOORE: [(isize, isize); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// Offsets of the four cells sharing an edge with a cell.
+pub const VON_NEUMANN: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Coord {
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Coord {
+    pub fn new(row: usize, col: usize) -> Coord {
+        Coord { row, col }
+    }
+
+    /// Moves the coordinate by `(rows, cols)`, or returns `None` if it would become negative.
+    pub fn offset(self, (rows, cols): (isize, isize)) -> Option<Coord> {
+        Some(Coord {
+            row: self.row.checked_add_signed(rows)?,
+            col: self.col.checked_add_signed(cols)?,
+        })
+    }
+}
+
+impl From<(usize, usize)> for Coord {
+    fn from((row, col): (usize, usize)) -> Coord {
+        Coord { row, col }
+    }
+}
+
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.row, self.col)
+    }
+}
+
+/// A dense, row-major grid of cells.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    rows: usize,
+    cols: usize,
+}
+
+impl<T> Grid<T> {
+    /// Creates a grid without rows, to be filled with [`Grid::push_row`].
+    pub fn with_cols(cols: usize) -> Grid<T> {
+        Grid {
+            cells: Vec::new(),
+            rows: 0,
+            cols,
+        }
+    }
+
+    pub fn from_fn(rows: usize, cols: usize, mut cell: impl FnMut(Coord) -> T) -> Grid<T> {
+        let mut cells = Vec::with_capacity(rows * cols);
+        for row in 0..rows {
+            for col in 0..cols {
+                cells.push(cell(Coord { row, col }));
+            }
+        }
+        Grid { cells, rows, cols }
+    }
+
+    /// Builds a grid from its rows, or returns `None` if they do not all have the same length.
+    pub fn from_rows(rows: impl IntoIterator<Item = Vec<T>>) -> Option<Grid<T>> {
+        let mut rows = rows.into_iter().peekable();
+        let mut grid = Grid::with_cols(rows.peek().map_or(0, Vec::len));
+        for row in rows {
+            if row.len() != grid.cols {
+                return None;
+            }
+            grid.push_row(row);
+        }
+        Some(grid)
+    }
+
+    /// Appends a row. Panics if its length differs from the number of columns.
+    pub fn push_row(&mut self, row: Vec<T>) {
+        assert_eq!(row.len(), self.cols, "row length must match the grid");
+        self.cells.extend(row);
+        self.rows += 1;
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn contains(&self, coord: Coord) -> bool {
+        coord.row < self.rows && coord.col < self.cols
+    }
+
+    pub fn get(&self, coord: Coord) -> Option<&T> {
+        self.contains(coord)
+            .then(|| &self.cells[coord.row * self.cols + coord.col])
+    }
+
+    pub fn get_mut(&mut self, coord: Coord) -> Option<&mut T> {
+        if !self.contains(coord) {
+            return None;
+        }
+        Some(&mut self.cells[coord.row * self.cols + coord.col])
+    }
+
+    /// Returns the cell at a signed coordinate, wrapping around the edges like a torus.
+    /// Panics if the grid is empty.
+    pub fn get_wrapping(&self, row: isize, col: isize) -> &T {
+        let row = row.rem_euclid(self.rows as isize) as usize;
+        let col = col.rem_euclid(self.cols as isize) as usize;
+        &self.cells[row * self.cols + col]
+    }
+
+    /// Iterates over the in-bounds cells at the given offsets from `coord`.
+    pub fn neighbors<'a>(
+        &'a self,
+        coord: Coord,
+        offsets: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = (Coord, &'a T)> + 'a {
+        offsets.iter().filter_map(move |&offset| {
+            let neighbor = coord.offset(offset)?;
+            Some((neighbor, self.get(neighbor)?))
+        })
+    }
+
+    /// Iterates over the cells at the given offsets from `coord`, wrapping around the edges.
+    /// An empty grid has no neighbours.
+    pub fn neighbors_wrapping<'a>(
+        &'a self,
+        coord: Coord,
+        offsets: &'a [(isize, isize)],
+    ) -> impl Iterator<Item = (Coord, &'a T)> + 'a {
+        let offsets = if self.cells.is_empty() { &[] } else { offsets };
+        offsets.iter().map(move |&(rows, cols)| {
+            let row = (coord.row as isize + rows).rem_euclid(self.rows as isize) as usize;
+            let col = (coord.col as isize + cols).rem_euclid(self.cols as isize) as usize;
+            let neighbor = Coord { row, col };
+            (neighbor, &self[neighbor])
+        })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Coord, &T)> {
+        let cols = self.cols;
+        self.cells.iter().enumerate().map(move |(index, cell)| {
+            let coord = Coord::new(index / cols, index % cols);
+            (coord, cell)
+        })
+    }
+
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.cols..(row + 1) * self.cols]
+    }
+
+    pub fn row_iter(&self) -> impl Iterator<Item = &[T]> {
+        (0..self.rows).map(|row| self.row(row))
+    }
+
+    pub fn column(&self, col: usize) -> impl Iterator<Item = &T> {
+        assert!(col < self.cols, "column out of bounds");
+        self.cells.iter().skip(col).step_by(self.cols)
+    }
+
+    /// Iterates over the cells of the `rows` x `cols` region starting at `top_left`, clipped to
+    /// the grid.
+    pub fn region(
+        &self,
+        top_left: Coord,
+        rows: usize,
+        cols: usize,
+    ) -> impl Iterator<Item = (Coord, &T)> {
+        let row_end = top_left.row.saturating_add(rows).min(self.rows);
+        let col_end = top_left.col.saturating_add(cols).min(self.cols);
+        (top_left.row..row_end).flat_map(move |row| {
+            (top_left.col..col_end).map(move |col| {
+                let coord = Coord { row, col };
+                (coord, &self[coord])
+            })
+        })
+    }
+
+    pub fn map<U>(&self, mut f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            cells: self.cells.iter().map(&mut f).collect(),
+            rows: self.rows,
+            cols: self.cols,
+        }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(rows: usize, cols: usize, fill: T) -> Grid<T> {
+        Grid {
+            cells: vec![fill; rows * cols],
+            rows,
+            cols,
+        }
+    }
+
+    pub fn transpose(&self) -> Grid<T> {
+        Grid::from_fn(self.cols, self.rows, |coord| {
+            self[Coord::new(coord.col, coord.row)].clone()
+        })
+    }
+
+    pub fn rotate_clockwise(&self) -> Grid<T> {
+        Grid::from_fn(self.cols, self.rows, |coord| {
+            self[Coord::new(self.rows - 1 - coord.col, coord.row)].clone()
+        })
+    }
+
+    pub fn rotate_counter_clockwise(&self) -> Grid<T> {
+        Grid::from_fn(self.cols, self.rows, |coord| {
+            self[Coord::new(coord.col, self.cols - 1 - coord.row)].clone()
+        })
+    }
+}
+
+impl<T> Index<Coord> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, coord: Coord) -> &T {
+        self.get(coord)
+            .unwrap_or_else(|| panic!("{} is outside the grid", coord))
+    }
+}
+
+impl<T> IndexMut<Coord> for Grid<T> {
+    fn index_mut(&mut self, coord: Coord) -> &mut T {
+        self.get_mut(coord)
+            .unwrap_or_else(|| panic!("{} is outside the grid", coord))
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.row_iter() {
+            for cell in row {
+                write!(f, "{}", cell)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn letters() -> Grid<char> {
+        Grid::from_rows(["abc", "def"].map(|row| row.chars().collect())).unwrap()
+    }
+
+    #[test]
+    fn test_from_rows() {
+        let grid = letters();
+        assert_eq!(grid.rows(), 2);
+        assert_eq!(grid.cols(), 3);
+        assert_eq!(grid[Coord::new(1, 2)], 'f');
+        assert_eq!(grid.to_string(), "abc\ndef\n");
+        assert_eq!(Grid::from_rows(vec![vec![1, 2], vec![3]]), None);
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut grid = letters();
+        assert_eq!(grid.get(Coord::new(2, 0)), None);
+        assert_eq!(grid.get(Coord::new(0, 3)), None);
+        assert_eq!(grid.get_wrapping(-1, -1), &'f');
+        assert_eq!(grid.get_wrapping(2, 4), &'b');
+
+        *grid.get_mut(Coord::new(0, 1)).unwrap() = 'x';
+        grid[Coord::new(1, 1)] = 'y';
+        assert_eq!(grid.to_string(), "axc\ndyf\n");
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let grid = letters();
+        let neighbors: String = grid
+            .neighbors(Coord::new(0, 0), &MOORE)
+            .map(|(_, c)| *c)
+            .collect();
+        assert_eq!(neighbors, "bde");
+
+        let neighbors: Vec<(Coord, char)> = grid
+            .neighbors_wrapping(Coord::new(0, 0), &VON_NEUMANN)
+            .map(|(coord, c)| (coord, *c))
+            .collect();
+        assert_eq!(
+            neighbors,
+            vec![
+                (Coord::new(1, 0), 'd'),
+                (Coord::new(0, 2), 'c'),
+                (Coord::new(0, 1), 'b'),
+                (Coord::new(1, 0), 'd'),
+            ]
+        );
+
+        let empty: Grid<char> = Grid::with_cols(3);
+        assert_eq!(
+            empty.neighbors_wrapping(Coord::new(0, 0), &MOORE).count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_iteration() {
+        let grid = letters();
+        assert_eq!(grid.row(1), &['d', 'e', 'f']);
+        assert_eq!(grid.column(1).collect::<String>(), "be");
+        assert_eq!(grid.row_iter().count(), 2);
+        assert_eq!(grid.iter().last(), Some((Coord::new(1, 2), &'f')));
+        let region: String = grid
+            .region(Coord::new(0, 1), 5, 5)
+            .map(|(_, c)| *c)
+            .collect();
+        assert_eq!(region, "bcef");
+    }
+
+    #[test]
+    fn test_transpose_and_rotate() {
+        let grid = letters();
+        assert_eq!(grid.transpose().to_string(), "ad\nbe\ncf\n");
+        assert_eq!(grid.rotate_clockwise().to_string(), "da\neb\nfc\n");
+        assert_eq!(grid.rotate_counter_clockwise().to_string(), "cf\nbe\nad\n");
+        assert_eq!(grid.rotate_clockwise().rotate_counter_clockwise(), grid);
+    }
+
+    #[test]
+    fn test_map() {
+        let grid = letters().map(|c| c.is_ascii_uppercase() || *c == 'e');
+        assert!(grid[Coord::new(1, 1)]);
+        assert_eq!(grid.iter().filter(|(_, cell)| **cell).count(), 1);
+    }
+}
@@ -0,0 +1,517 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+/// A set of `u64` values, stored as sorted inclusive intervals that neither overlap nor touch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IntervalSet {
+    // Sorted by start, with a gap of at least one value between consecutive intervals
+    intervals: Vec<(u64, u64)>,
+    // Number of values in all intervals, kept up to date so that it is cheap to query after
+    // every change
+    len: u128,
+}
+
+fn interval_len((start, end): (u64, u64)) -> u128 {
+    (end - start) as u128 + 1
+}
+
+impl IntervalSet {
+    pub fn new() -> IntervalSet {
+        IntervalSet::default()
+    }
+
+    fn from_sorted(intervals: Vec<(u64, u64)>) -> IntervalSet {
+        let len = intervals.iter().copied().map(interval_len).sum();
+        IntervalSet { intervals, len }
+    }
+
+    /// Adds all values of `range`, merging it with the intervals it overlaps or touches.
+    /// Empty ranges are ignored.
+    pub fn insert(&mut self, range: RangeInclusive<u64>) {
+        let (mut start, mut end) = range.into_inner();
+        if start > end {
+            return;
+        }
+
+        // Intervals ending right before `start` or later and starting right after `end` or
+        // earlier get merged with the new one
+        let first = self
+            .intervals
+            .partition_point(|&(_, last)| last.saturating_add(1) < start);
+        let after = self
+            .intervals
+            .partition_point(|&(first, _)| first <= end.saturating_add(1));
+        if first < after {
+            start = start.min(self.intervals[first].0);
+            end = end.max(self.intervals[after - 1].1);
+        }
+        let merged: u128 = self
+            .intervals
+            .splice(first..after, [(start, end)])
+            .map(interval_len)
+            .sum();
+        self.len = self.len - merged + interval_len((start, end));
+    }
+
+    /// Removes all values of `range`, shortening or splitting the intervals it overlaps.
+    /// Empty ranges are ignored.
+    pub fn remove(&mut self, range: RangeInclusive<u64>) {
+        let (start, end) = range.into_inner();
+        if start > end {
+            return;
+        }
+
+        let first = self.intervals.partition_point(|&(_, last)| last < start);
+        let after = self.intervals.partition_point(|&(first, _)| first <= end);
+        if first == after {
+            return;
+        }
+
+        // Parts of the first and last overlapped interval outside of `range` are kept
+        let mut kept = Vec::with_capacity(2);
+        let (head, _) = self.intervals[first];
+        let (_, tail) = self.intervals[after - 1];
+        if head < start {
+            kept.push((head, start - 1));
+        }
+        if tail > end {
+            kept.push((end + 1, tail));
+        }
+        let kept_len: u128 = kept.iter().copied().map(interval_len).sum();
+        let removed: u128 = self
+            .intervals
+            .splice(first..after, kept)
+            .map(interval_len)
+            .sum();
+        self.len = self.len - removed + kept_len;
+    }
+
+    pub fn contains(&self, value: u64) -> bool {
+        let index = self.intervals.partition_point(|&(_, end)| end < value);
+        self.intervals
+            .get(index)
+            .is_some_and(|&(start, _)| start <= value)
+    }
+
+    /// Counts how many of `values` are in the set in a single sweep over the values and the
+    /// intervals, which is faster than calling [`IntervalSet::contains`] for each value. The
+    /// values must be sorted in ascending order.
+    pub fn count_sorted(&self, values: &[u64]) -> usize {
+        debug_assert!(values.is_sorted(), "values must be sorted");
+        let mut intervals = self.intervals.iter().peekable();
+        values
+            .iter()
+            .filter(|&&value| {
+                while intervals.next_if(|&&(_, end)| end < value).is_some() {}
+                intervals.peek().is_some_and(|&&(start, _)| start <= value)
+            })
+            .count()
+    }
+
+    /// Returns a cursor for looking up a stream of values one at a time.
+    pub fn cursor(&self) -> Cursor<'_> {
+        Cursor {
+            intervals: &self.intervals,
+            index: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Returns the number of values in the set, which is `2^64` if it holds every `u64`.
+    pub fn len(&self) -> u128 {
+        self.len
+    }
+
+    /// Returns the number of disjoint intervals the set consists of.
+    pub fn interval_count(&self) -> usize {
+        self.intervals.len()
+    }
+
+    /// Iterates over the intervals of the set in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = RangeInclusive<u64>> + '_ {
+        self.intervals.iter().map(|&(start, end)| start..=end)
+    }
+
+    pub fn union(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = Vec::with_capacity(self.intervals.len() + other.intervals.len());
+        let (mut left, mut right) = (
+            self.intervals.iter().peekable(),
+            other.intervals.iter().peekable(),
+        );
+        loop {
+            let next = match (left.peek(), right.peek()) {
+                (Some(a), Some(b)) if a.0 <= b.0 => left.next(),
+                (Some(_), Some(_)) => right.next(),
+                (Some(_), None) => left.next(),
+                (None, Some(_)) => right.next(),
+                (None, None) => break,
+            };
+            push_merged(&mut intervals, *next.unwrap());
+        }
+        IntervalSet::from_sorted(intervals)
+    }
+
+    pub fn intersection(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.intervals.len() && j < other.intervals.len() {
+            let (a_start, a_end) = self.intervals[i];
+            let (b_start, b_end) = other.intervals[j];
+            let (start, end) = (a_start.max(b_start), a_end.min(b_end));
+            if start <= end {
+                intervals.push((start, end));
+            }
+            if a_end < b_end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet::from_sorted(intervals)
+    }
+
+    /// Returns the values of `self` that are not in `other`.
+    pub fn difference(&self, other: &IntervalSet) -> IntervalSet {
+        let mut intervals = Vec::new();
+        let mut j = 0;
+        for &(start, end) in self.intervals.iter() {
+            // Skip the intervals of `other` that end before this one starts
+            while j < other.intervals.len() && other.intervals[j].1 < start {
+                j += 1;
+            }
+
+            let mut from = Some(start);
+            for &(cut_start, cut_end) in other.intervals[j..]
+                .iter()
+                .take_while(|&&(cut_start, _)| cut_start <= end)
+            {
+                let Some(current) = from else { break };
+                if cut_start > current {
+                    intervals.push((current, cut_start - 1));
+                }
+                from = cut_end.checked_add(1).filter(|&next| next <= end);
+            }
+            if let Some(from) = from {
+                intervals.push((from, end));
+            }
+        }
+        IntervalSet::from_sorted(intervals)
+    }
+
+    /// Returns the values within `bounds` that are not in the set.
+    pub fn complement(&self, bounds: RangeInclusive<u64>) -> IntervalSet {
+        let mut within = IntervalSet::new();
+        within.insert(bounds);
+        within.difference(self)
+    }
+}
+
+/// Looks up values in an [`IntervalSet`] starting from where the previous lookup ended, so
+/// ascending values are found in about constant time each without having to be buffered
+/// and sorted first. Values that go back are looked up with a binary search.
+#[derive(Debug, Clone)]
+pub struct Cursor<'a> {
+    intervals: &'a [(u64, u64)],
+    // Index of the first interval that does not end before the last value
+    index: usize,
+}
+
+impl Cursor<'_> {
+    pub fn contains(&mut self, value: u64) -> bool {
+        let intervals = self.intervals;
+        if self.index > 0 && intervals[self.index - 1].1 >= value {
+            self.index = intervals.partition_point(|&(_, end)| end < value);
+        } else {
+            // Gallop forward to find a window containing the interval, then search it
+            let mut step = 1;
+            while self.index + step < intervals.len() && intervals[self.index + step].1 < value {
+                step *= 2;
+            }
+            let window = &intervals[self.index..intervals.len().min(self.index + step + 1)];
+            self.index += window.partition_point(|&(_, end)| end < value);
+        }
+        intervals
+            .get(self.index)
+            .is_some_and(|&(start, _)| start <= value)
+    }
+}
+
+// Appends an interval that starts no earlier than the last one, merging them if they overlap
+// or touch
+fn push_merged(intervals: &mut Vec<(u64, u64)>, (start, end): (u64, u64)) {
+    match intervals.last_mut() {
+        Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+        _ => intervals.push((start, end)),
+    }
+}
+
+impl FromIterator<RangeInclusive<u64>> for IntervalSet {
+    /// Builds the set by sorting the ranges once, which is faster than inserting them one by
+    /// one.
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<u64>>>(ranges: I) -> IntervalSet {
+        let mut sorted: Vec<(u64, u64)> = ranges
+            .into_iter()
+            .map(RangeInclusive::into_inner)
+            .filter(|(start, end)| start <= end)
+            .collect();
+        sorted.sort_unstable();
+
+        let mut intervals = Vec::with_capacity(sorted.len());
+        for interval in sorted {
+            push_merged(&mut intervals, interval);
+        }
+        IntervalSet::from_sorted(intervals)
+    }
+}
+
+impl Extend<RangeInclusive<u64>> for IntervalSet {
+    fn extend<I: IntoIterator<Item = RangeInclusive<u64>>>(&mut self, ranges: I) {
+        for range in ranges {
+            self.insert(range);
+        }
+    }
+}
+
+/// Prints the intervals as `start-end`, separated by commas.
+impl fmt::Display for IntervalSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, (start, end)) in self.intervals.iter().enumerate() {
+            if index > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}-{}", start, end)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::XorShift;
+
+    fn set(ranges: &[RangeInclusive<u64>]) -> IntervalSet {
+        ranges.iter().cloned().collect()
+    }
+
+    // Brute force version of a set over small values
+    fn values(set: &IntervalSet) -> Vec<u64> {
+        (0..64).filter(|&value| set.contains(value)).collect()
+    }
+
+    #[test]
+    fn test_insert_merges_overlapping_and_touching() {
+        let mut intervals = IntervalSet::new();
+        intervals.insert(10..=14);
+        intervals.insert(3..=5);
+        intervals.insert(16..=20);
+        assert_eq!(intervals.to_string(), "3-5,10-14,16-20");
+
+        intervals.insert(12..=18);
+        assert_eq!(intervals.to_string(), "3-5,10-20");
+        intervals.insert(6..=9);
+        assert_eq!(intervals.to_string(), "3-20");
+        intervals.insert(RangeInclusive::new(8, 7));
+        assert_eq!(intervals.interval_count(), 1);
+        assert_eq!(intervals.len(), 18);
+    }
+
+    #[test]
+    fn test_from_iter_matches_insert() {
+        let ranges = [
+            1..=3,
+            2..=5,
+            10..=12,
+            11..=15,
+            17..=17,
+            16..=16,
+            RangeInclusive::new(30, 29),
+        ];
+        let mut inserted = IntervalSet::new();
+        inserted.extend(ranges.iter().cloned());
+        assert_eq!(set(&ranges), inserted);
+        assert_eq!(inserted.iter().collect::<Vec<_>>(), vec![1..=5, 10..=17]);
+    }
+
+    #[test]
+    fn test_contains() {
+        let intervals = set(&[3..=5, 10..=14, 16..=20, 12..=18]);
+        let fresh: Vec<u64> = [1, 5, 8, 11, 17, 32]
+            .into_iter()
+            .filter(|&id| intervals.contains(id))
+            .collect();
+        assert_eq!(fresh, vec![5, 11, 17]);
+        assert!(!IntervalSet::new().contains(0));
+    }
+
+    #[test]
+    fn test_count_sorted_matches_contains() {
+        let intervals = set(&[3..=5, 10..=14, 16..=20, 12..=18, 40..=40]);
+        let values: Vec<u64> = vec![0, 1, 3, 5, 5, 8, 11, 17, 21, 39, 40, 41, 100];
+        let expected = values.iter().filter(|&&v| intervals.contains(v)).count();
+        assert_eq!(intervals.count_sorted(&values), expected);
+        assert_eq!(expected, 6);
+        assert_eq!(IntervalSet::new().count_sorted(&values), 0);
+    }
+
+    #[test]
+    fn test_cursor_matches_contains() {
+        let intervals = set(&[3..=5, 10..=14, 16..=20, 24..=24, 30..=31, 40..=50, 60..=60]);
+        let mut cursor = intervals.cursor();
+        let values = [
+            0,
+            3,
+            4,
+            4,
+            12,
+            21,
+            24,
+            45,
+            61,
+            62,
+            2,
+            3,
+            30,
+            29,
+            60,
+            17,
+            5,
+            55,
+            u64::MAX,
+        ];
+        for value in values {
+            assert_eq!(
+                cursor.contains(value),
+                intervals.contains(value),
+                "{}",
+                value
+            );
+        }
+        assert!(!IntervalSet::new().cursor().contains(0));
+    }
+
+    #[test]
+    fn test_extreme_bounds() {
+        let max = u64::MAX;
+        let full = set(&[0..=max]);
+        assert_eq!(full.len(), 1 << 64);
+        assert!(full.contains(0) && full.contains(max));
+
+        let mut intervals = IntervalSet::new();
+        intervals.insert(max..=max);
+        intervals.insert(0..=0);
+        assert_eq!(intervals.len(), 2);
+        assert!(intervals.contains(max) && !intervals.contains(max - 1));
+        assert_eq!(intervals.count_sorted(&[0, 1, max - 1, max, max]), 3);
+
+        intervals.insert(1..=max - 1);
+        assert_eq!(intervals, full);
+        assert_eq!(set(&[max - 1..=max, 0..=max - 2]), full);
+        assert_eq!(set(&[5..=10, 0..=max, max..=max]).len(), 1 << 64);
+
+        let ends = set(&[0..=0, max..=max]);
+        assert_eq!(full.difference(&ends).to_string(), format!("1-{}", max - 1));
+        assert_eq!(full.difference(&full), IntervalSet::new());
+        assert_eq!(ends.difference(&full), IntervalSet::new());
+        assert_eq!(full.intersection(&ends), ends);
+        assert_eq!(ends.union(&set(&[1..=max - 1])), full);
+        assert_eq!(full.difference(&ends).len(), (1 << 64) - 2);
+    }
+
+    #[test]
+    fn test_remove_splits_intervals() {
+        let mut intervals = set(&[3..=5, 10..=20, 30..=40]);
+        intervals.remove(4..=4);
+        assert_eq!(intervals.to_string(), "3-3,5-5,10-20,30-40");
+        intervals.remove(15..=32);
+        assert_eq!(intervals.to_string(), "3-3,5-5,10-14,33-40");
+        intervals.remove(0..=10);
+        assert_eq!(intervals.to_string(), "11-14,33-40");
+        intervals.remove(20..=25);
+        intervals.remove(RangeInclusive::new(13, 12));
+        assert_eq!(intervals.len(), 12);
+        intervals.remove(0..=u64::MAX);
+        assert_eq!(intervals, IntervalSet::new());
+
+        let mut full = set(&[0..=u64::MAX]);
+        full.remove(0..=0);
+        full.remove(u64::MAX..=u64::MAX);
+        assert_eq!(full.len(), (1 << 64) - 2);
+        full.insert(0..=u64::MAX);
+        assert_eq!(full.len(), 1 << 64);
+    }
+
+    // A mix of insertions and removals must match a brute force set, including its length
+    #[test]
+    fn test_insert_and_remove_match_values() {
+        let mut intervals = IntervalSet::new();
+        let mut expected = [false; 64];
+        let mut random = XorShift::new(7);
+        for _ in 0..500 {
+            let state = random.next_u32();
+            let start = (state % 60) as u64;
+            let end = start + (state >> 8) as u64 % 4;
+            if state.is_multiple_of(3) {
+                intervals.remove(start..=end);
+            } else {
+                intervals.insert(start..=end);
+            }
+            for value in start..=end {
+                expected[value as usize] = !state.is_multiple_of(3);
+            }
+
+            let expected_values: Vec<u64> = (0..64).filter(|&v| expected[v as usize]).collect();
+            assert_eq!(values(&intervals), expected_values);
+            assert_eq!(intervals.len(), expected_values.len() as u128);
+            assert_eq!(intervals, set(&intervals.iter().collect::<Vec<_>>()));
+        }
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = set(&[0..=4, 8..=12, 20..=30, 40..=40]);
+        let b = set(&[3..=9, 12..=21, 25..=26, 31..=35, 50..=60]);
+        let (a_values, b_values) = (values(&a), values(&b));
+
+        let union: Vec<u64> = (0..64)
+            .filter(|v| a_values.contains(v) || b_values.contains(v))
+            .collect();
+        let intersection: Vec<u64> = a_values
+            .iter()
+            .copied()
+            .filter(|v| b_values.contains(v))
+            .collect();
+        let difference: Vec<u64> = a_values
+            .iter()
+            .copied()
+            .filter(|v| !b_values.contains(v))
+            .collect();
+
+        assert_eq!(values(&a.union(&b)), union);
+        assert_eq!(a.union(&b).to_string(), "0-35,40-40,50-60");
+        assert_eq!(values(&a.intersection(&b)), intersection);
+        assert_eq!(a.intersection(&b).to_string(), "3-4,8-9,12-12,20-21,25-26");
+        assert_eq!(values(&a.difference(&b)), difference);
+        assert_eq!(a.difference(&b).to_string(), "0-2,10-11,22-24,27-30,40-40");
+        assert_eq!(b.difference(&a).difference(&b), IntervalSet::new());
+    }
+
+    #[test]
+    fn test_complement() {
+        let intervals = set(&[3..=5, 10..=14, 16..=20, 12..=18]);
+        assert_eq!(intervals.complement(0..=30).to_string(), "0-2,6-9,21-30");
+        assert_eq!(intervals.complement(4..=12).to_string(), "6-9");
+        assert_eq!(intervals.complement(11..=19), IntervalSet::new());
+        assert_eq!(
+            intervals.complement(RangeInclusive::new(8, 7)),
+            IntervalSet::new()
+        );
+        assert_eq!(
+            intervals.complement(0..=u64::MAX).len(),
+            (1 << 64) - intervals.len()
+        );
+    }
+}
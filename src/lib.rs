@@ -0,0 +1,15 @@
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod grid;
+pub mod interval_set;
+
+/// The part of a day's puzzle that a binary solves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    One,
+    Two,
+}
+
+#[cfg(test)]
+mod testing;
@@ -0,0 +1,43 @@
+//! Reproducible random inputs for the tests, and for the benchmarks, which include this file.
+
+/// A small xorshift generator, so that random inputs are the same in every run.
+pub(crate) struct XorShift(u64);
+
+impl XorShift {
+    /// The seed must not be 0, which would only ever produce 0.
+    pub(crate) fn new(seed: u64) -> XorShift {
+        assert_ne!(seed, 0, "the seed must not be 0");
+        XorShift(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns the upper half of the next number, which is more random than the lower one.
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Returns `true` with a probability of `percent` percent.
+    pub(crate) fn chance(&mut self, percent: u32) -> bool {
+        self.next_u64() % 100 < percent as u64
+    }
+}
+
+/// Returns a grid of `rows` lines of `cols` cells, each of which is a roll `@` with a
+/// probability of `density` percent and empty `.` otherwise.
+pub(crate) fn random_rolls(seed: u64, rows: usize, cols: usize, density: u32) -> String {
+    let mut random = XorShift::new(seed);
+    let mut grid = String::with_capacity(rows * (cols + 1));
+    for _ in 0..rows {
+        for _ in 0..cols {
+            grid.push(if random.chance(density) { '@' } else { '.' });
+        }
+        grid.push('\n');
+    }
+    grid
+}